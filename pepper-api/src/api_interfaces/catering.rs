@@ -0,0 +1,33 @@
+use serde::Deserialize;
+
+/// Raw catering menu data from API.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Response {
+    pub restaurant_id: i32,
+    #[serde(default)]
+    pub catering_items: Vec<Item>,
+}
+
+/// Raw catering item from API. Catering items are priced per person or per package rather than per order.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Item {
+    pub item_category: String,
+    pub item_type: String,
+    pub item_id: String,
+    pub item_name: String,
+    pub unit_price: f32,
+    /// Fewest units that can be ordered, e.g. a build-your-own bar for at least 10 people.
+    #[serde(default)]
+    pub minimum_quantity: Option<u32>,
+    /// People one unit serves, for items sold by the package.
+    #[serde(default)]
+    pub serves: Option<u32>,
+    #[serde(alias = "isItemAvailable", default = "available_by_default")]
+    pub is_available: bool,
+}
+
+fn available_by_default() -> bool {
+    true
+}
@@ -3,23 +3,35 @@ use serde::Deserialize;
 // Request structure is omitted since we use a single request structure for all requests.
 
 /// Raw response from API.
-#[derive(Deserialize)]
+#[derive(Debug, Deserialize)]
 pub struct Response {
     pub data: Vec<Location>,
 }
 
 /// Raw location data from API.
-#[derive(Deserialize)]
+#[derive(Debug, Deserialize)]
 pub struct Location {
     #[serde(alias = "restaurantNumber")]
     pub id: i32,
+    #[serde(alias = "restaurantName", default)]
+    pub name: Option<String>,
     pub addresses: Vec<Address>,
 }
 
 /// Raw address data from API.
-#[derive(Deserialize)]
+#[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Address {
+    #[serde(alias = "addressLine1", default)]
+    pub street: Option<String>,
+    #[serde(alias = "locality", default)]
+    pub city: Option<String>,
+    #[serde(alias = "administrativeArea", default)]
+    pub state: Option<String>,
     pub postal_code: Option<String>,
     pub country_code: String,
+    #[serde(default)]
+    pub latitude: Option<f64>,
+    #[serde(default)]
+    pub longitude: Option<f64>,
 }
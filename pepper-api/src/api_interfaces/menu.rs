@@ -1,9 +1,10 @@
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
 
 // Request structure is omitted since we use a single request structure for all requests.
 
 /// Raw restaurant menu data from API.
-#[derive(Deserialize)]
+#[derive(Clone, Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Response {
     pub restaurant_id: i32,
@@ -12,7 +13,7 @@ pub struct Response {
 }
 
 /// Raw item from API.
-#[derive(Deserialize)]
+#[derive(Clone, Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Item {
     pub item_category: String,
@@ -21,4 +22,44 @@ pub struct Item {
     pub item_name: String,
     pub unit_price: f32,
     pub unit_delivery_price: f32,
+    /// Whether the store currently sells the item. Missing from older responses, where every item was sold.
+    #[serde(alias = "isItemAvailable", default = "available_by_default")]
+    pub is_available: bool,
+    /// Missing for stores without nutrition data. Ranges like `"180-270"` or `{"min": 180, "max": 270}`
+    /// give their lower bound, and anything unrecognized is `None` rather than failing the whole menu.
+    #[serde(default, deserialize_with = "lenient_calories")]
+    pub calories: Option<u32>,
+}
+
+fn lenient_calories<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u32>, D::Error> {
+    Ok(calories_lower_bound(&Value::deserialize(deserializer)?))
+}
+
+fn calories_lower_bound(value: &Value) -> Option<u32> {
+    match value {
+        Value::Number(number) => number
+            .as_f64()
+            .filter(|calories| {
+                calories.is_finite() && *calories >= 0.0 && *calories <= u32::MAX as f64
+            })
+            .map(|calories| calories as u32),
+        Value::String(text) => {
+            let digits: String = text
+                .trim()
+                .chars()
+                .take_while(char::is_ascii_digit)
+                .collect();
+            digits.parse().ok()
+        }
+        Value::Object(range) => ["min", "minimum", "low"]
+            .iter()
+            .find_map(|key| range.get(*key))
+            .and_then(calories_lower_bound),
+        Value::Array(values) => values.first().and_then(calories_lower_bound),
+        _ => None,
+    }
+}
+
+fn available_by_default() -> bool {
+    true
 }
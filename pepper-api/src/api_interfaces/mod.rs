@@ -1,2 +1,3 @@
+pub mod catering;
 pub mod locations;
 pub mod menu;
@@ -1,20 +1,87 @@
-use std::sync::LazyLock;
+use std::{
+    fmt, io,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, LazyLock, Mutex,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
-use crate::util::default_http_client;
+#[cfg(feature = "blocking")]
+use crate::util::error_body_blocking;
+use crate::{
+    constants::{API_KEY_ENV_VAR, API_KEY_HEADER},
+    error::body_suffix,
+    util::{default_http_client, error_body},
+};
 
 use regex::Regex;
-use reqwest::Client;
+use reqwest::{
+    header::{HeaderMap, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED},
+    Client, RequestBuilder, StatusCode,
+};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use tracing::Instrument;
 
 const DEFAULT_API_KEY_ENDPOINT: &str = "https://orderweb-cdn.chipotle.com/js/app.js";
 
-const API_KEY_PATTERN: &str = r#"gatewaySubscriptionKey:Q\("([a-zA-Z0-9-]+)"\)"#;
-static API_KEY_REGEX: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(API_KEY_PATTERN).expect("Invalid regex pattern"));
+/// Patterns for the API key in the client bundle, tried in order. The key is the first capture group.
+/// The bundle is minified, so the wrapper function around the key gets renamed from time to time.
+const API_KEY_PATTERNS: [&str; 3] = [
+    r#"gatewaySubscriptionKey:Q\("([a-zA-Z0-9-]+)"\)"#,
+    r#"gatewaySubscriptionKey:[A-Za-z_$]+\("([a-zA-Z0-9-]+)"\)"#,
+    r#"gatewaySubscriptionKey:"([a-zA-Z0-9-]+)""#,
+];
+/// Lengths accepted for a key extracted from the bundle. Real keys are 32 characters.
+const API_KEY_LEN: RangeInclusive<usize> = 12..=64;
 
-#[derive(Clone, Debug)]
+static API_KEY_REGEXES: LazyLock<Vec<Regex>> = LazyLock::new(|| {
+    API_KEY_PATTERNS
+        .iter()
+        .map(|pattern| Regex::new(pattern).expect("Invalid regex pattern"))
+        .collect()
+});
+
+/// A key for the Chipotle APIs. `Debug` and `Display` only show the last 4 characters, so it stays out of logs;
+/// use [`ApiKey::get`] for the full key. Serializing it as the full key needs the `serialize-api-key` feature.
+#[derive(Clone, PartialEq, Eq)]
 pub struct ApiKey {
-    key: String,
+    key: Option<String>,
+}
+
+/// Keys shorter than this are redacted completely, since their last 4 characters would give too much away.
+const MIN_PARTIALLY_SHOWN_KEY_LEN: usize = 12;
+
+impl fmt::Display for ApiKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.key {
+            None => f.write_str("unauthenticated"),
+            Some(key) if key.chars().count() < MIN_PARTIALLY_SHOWN_KEY_LEN => {
+                f.write_str("***redacted***")
+            }
+            Some(key) => {
+                let last_four: String = key.chars().skip(key.chars().count() - 4).collect();
+                write!(f, "***{last_four}")
+            }
+        }
+    }
+}
+
+impl fmt::Debug for ApiKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ApiKey({self})")
+    }
+}
+
+/// Serializes as the full key, or `null` for an unauthenticated placeholder.
+#[cfg(feature = "serialize-api-key")]
+impl Serialize for ApiKey {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.key.serialize(serializer)
+    }
 }
 
 impl ApiKey {
@@ -27,55 +94,436 @@ impl ApiKey {
     /// Retrieve the API key using custom HTTP client and endpoint.
     /// If the endpoint is not provided, the default Chipotle client bundle URL will be used.
     pub async fn get_custom(client: &Client, endpoint: Option<&str>) -> Result<Self, ApiKeyError> {
-        let response = client
-            .get(endpoint.unwrap_or(DEFAULT_API_KEY_ENDPOINT))
-            .send()
-            .await?;
+        Self::get_with_regexes(client, endpoint, &API_KEY_REGEXES).await
+    }
+
+    /// Retrieve the API key using custom HTTP client and endpoint, trying each pattern in order.
+    /// Each pattern must capture the key in its first group.
+    pub async fn get_custom_with_patterns(
+        client: &Client,
+        endpoint: Option<&str>,
+        patterns: &[&str],
+    ) -> Result<Self, ApiKeyError> {
+        let regexes = patterns
+            .iter()
+            .map(|pattern| Regex::new(pattern))
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::get_with_regexes(client, endpoint, &regexes).await
+    }
+
+    /// Retrieve the API key only if the client bundle changed since `cache` was recorded.
+    /// Sends `If-None-Match`/`If-Modified-Since` from `cache`, so an unchanged bundle costs a `304` instead of a full download.
+    pub async fn get_custom_if_modified(
+        client: &Client,
+        endpoint: Option<&str>,
+        cache: &ApiKeyCache,
+    ) -> Result<ApiKeyFetch, ApiKeyError> {
+        Ok(
+            match Self::fetch(client, endpoint, &API_KEY_REGEXES, Some(cache)).await? {
+                Some((key, cache)) => ApiKeyFetch::Fetched { key, cache },
+                None => ApiKeyFetch::NotModified,
+            },
+        )
+    }
+
+    async fn get_with_regexes(
+        client: &Client,
+        endpoint: Option<&str>,
+        regexes: &[Regex],
+    ) -> Result<Self, ApiKeyError> {
+        let (key, _) = Self::fetch_unconditional(client, endpoint, regexes).await?;
+        Ok(key)
+    }
+
+    async fn fetch_unconditional(
+        client: &Client,
+        endpoint: Option<&str>,
+        regexes: &[Regex],
+    ) -> Result<(Self, ApiKeyCache), ApiKeyError> {
+        Ok(Self::fetch(client, endpoint, regexes, None)
+            .await?
+            .expect("unconditional requests are never answered with 304"))
+    }
+
+    /// Fetch the bundle and extract the key, along with the bundle's validators.
+    /// Returns `None` if a conditional request was answered with `304 Not Modified`.
+    async fn fetch(
+        client: &Client,
+        endpoint: Option<&str>,
+        regexes: &[Regex],
+        conditional: Option<&ApiKeyCache>,
+    ) -> Result<Option<(Self, ApiKeyCache)>, ApiKeyError> {
+        let url = endpoint.unwrap_or(DEFAULT_API_KEY_ENDPOINT);
+        let span = tracing::debug_span!("api_key_request", url);
+        let fetched = async {
+            let mut request = client.get(url);
+            if let Some(cache) = conditional {
+                request = cache.apply(request);
+            }
+            let response = request.send().await?;
+            tracing::debug!(status = %response.status(), "API key response");
+            if conditional.is_some() && response.status() == StatusCode::NOT_MODIFIED {
+                return Ok(None);
+            }
+            if !response.status().is_success() {
+                return Err(ApiKeyError::ResponseError {
+                    status: response.status(),
+                    body: error_body(response).await,
+                });
+            }
+            let cache = ApiKeyCache::from_headers(response.headers());
+            let body = response
+                .text()
+                .await
+                .map_err(ApiKeyError::ResponseBodyError)?;
+            Ok(Some((body, cache)))
+        }
+        .instrument(span)
+        .await?;
+        let Some((body, cache)) = fetched else {
+            return Ok(None);
+        };
+        Ok(Some((Self::from_bundle(&body, regexes)?, cache)))
+    }
+
+    /// Retrieve the API key with a blocking HTTP client, without needing an async runtime.
+    /// Behaves like [`ApiKey::get_custom`].
+    #[cfg(feature = "blocking")]
+    pub fn get_custom_blocking(
+        client: &reqwest::blocking::Client,
+        endpoint: Option<&str>,
+    ) -> Result<Self, ApiKeyError> {
+        let url = endpoint.unwrap_or(DEFAULT_API_KEY_ENDPOINT);
+        let _span = tracing::debug_span!("api_key_request", url).entered();
+        let response = client.get(url).send()?;
+        tracing::debug!(status = %response.status(), "API key response");
         if !response.status().is_success() {
-            return Err(ApiKeyError::ResponseError(response.status()));
+            return Err(ApiKeyError::ResponseError {
+                status: response.status(),
+                body: error_body_blocking(response),
+            });
         }
-        let body = response
-            .text()
-            .await
-            .map_err(ApiKeyError::ResponseBodyError)?;
-        let captures = API_KEY_REGEX
-            .captures(&body)
-            .ok_or(ApiKeyError::ApiKeyNotFound)?;
-        let key = captures
-            .get(1)
+        let body = response.text().map_err(ApiKeyError::ResponseBodyError)?;
+        Self::from_bundle(&body, &API_KEY_REGEXES)
+    }
+
+    /// Extract the key from the client bundle with the first pattern that matches.
+    /// What it captures has to look like a key, so a pattern matching the wrong thing fails here
+    /// rather than with a 403 on every later request.
+    fn from_bundle(body: &str, regexes: &[Regex]) -> Result<Self, ApiKeyError> {
+        let key = regexes
+            .iter()
+            .find_map(|regex| regex.captures(body)?.get(1))
             .map(|m| m.as_str().to_string())
             .ok_or(ApiKeyError::ApiKeyNotFound)?;
-        Ok(Self { key })
+        validate_format(&key)?;
+        Ok(Self { key: Some(key) })
+    }
+
+    /// Retrieve the API key from a cache file if it was fetched less than `ttl` ago.
+    /// Once the TTL has passed, the bundle is requested conditionally, so an unchanged bundle
+    /// only refreshes the cache's timestamp. Otherwise the key is fetched again and the cache rewritten.
+    /// A missing or unreadable cache file is treated as expired.
+    pub async fn get_cached<P: AsRef<Path>>(
+        client: &Client,
+        endpoint: Option<&str>,
+        cache_path: P,
+        ttl: Duration,
+    ) -> Result<Self, ApiKeyError> {
+        let cache_path = cache_path.as_ref();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let cached = CachedKey::read(cache_path).await;
+        let fetch = match &cached {
+            Some(cached) if now.saturating_sub(cached.fetched_at) < ttl.as_secs() => {
+                return Ok(Self::from_raw(&cached.key));
+            }
+            Some(cached) if !cached.validators.is_empty() => {
+                Self::get_custom_if_modified(client, endpoint, &cached.validators).await?
+            }
+            _ => {
+                let (key, cache) =
+                    Self::fetch_unconditional(client, endpoint, &API_KEY_REGEXES).await?;
+                ApiKeyFetch::Fetched { key, cache }
+            }
+        };
+        let (key, validators) = match fetch {
+            ApiKeyFetch::Fetched { key, cache } => (key, cache),
+            ApiKeyFetch::NotModified => {
+                let cached = cached.expect("only cached keys are fetched conditionally");
+                (Self::from_raw(&cached.key), cached.validators)
+            }
+        };
+        let cached = CachedKey {
+            key: key.get().to_string(),
+            fetched_at: now,
+            validators,
+        };
+        cached
+            .write(cache_path)
+            .await
+            .map_err(|source| ApiKeyError::CacheWriteError {
+                path: cache_path.to_path_buf(),
+                source,
+            })?;
+        Ok(key)
     }
 
     /// From a raw API key string.
     pub fn from_raw(key: &str) -> Self {
         Self {
-            key: key.to_owned(),
+            key: Some(key.to_owned()),
+        }
+    }
+
+    /// Read the API key from an environment variable. Returns `None` if it's unset, empty or not valid Unicode.
+    pub fn from_env(var_name: &str) -> Option<Self> {
+        let key = std::env::var(var_name).ok()?;
+        let key = key.trim();
+        (!key.is_empty()).then(|| Self::from_raw(key))
+    }
+
+    /// Read the API key from a file, ignoring surrounding whitespace such as the newline `echo` leaves.
+    /// Fails if the file can't be read or holds nothing but whitespace.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        match contents.trim() {
+            "" => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "the API key file is empty",
+            )),
+            key => Ok(Self::from_raw(key)),
         }
     }
 
+    /// Read the API key from the [`API_KEY_ENV_VAR`] environment variable, `CHIPOTLE_API_KEY`.
+    pub fn from_default_env() -> Option<Self> {
+        Self::from_env(API_KEY_ENV_VAR)
+    }
+
+    /// A placeholder key that sends no API key header at all.
+    /// Only meant for testing against mock endpoints that don't check auth.
+    pub fn unauthenticated() -> Self {
+        Self { key: None }
+    }
+
+    /// The raw key, or an empty string for an unauthenticated placeholder.
     pub fn get(&self) -> &str {
-        &self.key
+        self.key.as_deref().unwrap_or_default()
     }
+
+    /// Attach the API key header to a request, unless this is an unauthenticated placeholder.
+    pub(crate) fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.key {
+            Some(key) => request.header(API_KEY_HEADER, key),
+            None => request,
+        }
+    }
+
+    /// Blocking version of [`ApiKey::authorize`].
+    #[cfg(feature = "blocking")]
+    pub(crate) fn authorize_blocking(
+        &self,
+        request: reqwest::blocking::RequestBuilder,
+    ) -> reqwest::blocking::RequestBuilder {
+        match &self.key {
+            Some(key) => request.header(API_KEY_HEADER, key),
+            None => request,
+        }
+    }
+}
+
+/// The on-disk format of the API key cache.
+#[derive(Serialize, Deserialize)]
+struct CachedKey {
+    key: String,
+    /// Unix timestamp, in seconds, of when the key was fetched.
+    fetched_at: u64,
+    #[serde(default, flatten)]
+    validators: ApiKeyCache,
+}
+
+/// The validators of a previously downloaded client bundle, used to make the next request conditional.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiKeyCache {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+}
+
+impl ApiKeyCache {
+    /// Read the `ETag` and `Last-Modified` headers of a bundle response.
+    fn from_headers(headers: &HeaderMap) -> Self {
+        let header = |name| Some(headers.get(name)?.to_str().ok()?.to_string());
+        Self {
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+        }
+    }
+
+    /// Whether there is nothing to make a request conditional on.
+    pub fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+
+    /// Add `If-None-Match`/`If-Modified-Since` headers for the recorded validators.
+    fn apply(&self, mut request: RequestBuilder) -> RequestBuilder {
+        if let Some(etag) = &self.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &self.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+        request
+    }
+}
+
+/// The outcome of [`ApiKey::get_custom_if_modified`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ApiKeyFetch {
+    /// The bundle changed; here is the key in it and the validators for the next request.
+    Fetched { key: ApiKey, cache: ApiKeyCache },
+    /// The bundle is unchanged, so the previously fetched key is still current.
+    NotModified,
+}
+
+impl CachedKey {
+    async fn read(path: &Path) -> Option<Self> {
+        let contents = tokio::fs::read_to_string(path).await.ok()?;
+        serde_json::from_str(&contents).ok()
+    }
+
+    async fn write(&self, path: &Path) -> io::Result<()> {
+        tokio::fs::write(path, serde_json::to_string(self)?).await
+    }
+}
+
+/// A set of API keys handed out round-robin to spread requests across keys.
+/// Clones share the same rotation, so a key removed through one clone is removed for all.
+#[derive(Clone, Debug)]
+pub struct ApiKeyPool {
+    keys: Arc<Mutex<Vec<ApiKey>>>,
+    next: Arc<AtomicUsize>,
+}
+
+impl ApiKeyPool {
+    pub fn new(keys: Vec<ApiKey>) -> Self {
+        Self {
+            keys: Arc::new(Mutex::new(keys)),
+            next: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Get the next key in the rotation, or `None` if every key has been removed.
+    pub fn next_key(&self) -> Option<ApiKey> {
+        let keys = self.keys.lock().unwrap();
+        if keys.is_empty() {
+            return None;
+        }
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % keys.len();
+        Some(keys[index].clone())
+    }
+
+    /// Drop a key from the rotation.
+    pub fn remove(&self, key: &ApiKey) {
+        self.keys.lock().unwrap().retain(|k| k != key);
+    }
+
+    /// Drop a rejected key, unless it's the last one left. Returns whether another key is left to try.
+    pub(crate) fn remove_unless_last(&self, key: &ApiKey) -> bool {
+        let mut keys = self.keys.lock().unwrap();
+        match keys.iter().position(|k| k == key) {
+            Some(_) if keys.len() == 1 => false,
+            Some(index) => {
+                keys.remove(index);
+                true
+            }
+            None => !keys.is_empty(),
+        }
+    }
+
+    pub(crate) fn contains(&self, key: &ApiKey) -> bool {
+        self.keys.lock().unwrap().contains(key)
+    }
+
+    /// Swap `old` for `new`. Returns false if `old` is no longer in the pool.
+    pub(crate) fn replace(&self, old: &ApiKey, new: ApiKey) -> bool {
+        let mut keys = self.keys.lock().unwrap();
+        match keys.iter_mut().find(|k| *k == old) {
+            Some(key) => {
+                *key = new;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Check that an extracted key is a plausible length and only has the characters keys use.
+/// The key itself stays out of the error, like it stays out of logs.
+fn validate_format(key: &str) -> Result<(), ApiKeyError> {
+    if !API_KEY_LEN.contains(&key.len()) {
+        return Err(ApiKeyError::InvalidFormat(format!(
+            "{} characters long, expected {} to {}",
+            key.len(),
+            API_KEY_LEN.start(),
+            API_KEY_LEN.end()
+        )));
+    }
+    if !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(ApiKeyError::InvalidFormat(
+            "has characters other than letters, digits and dashes".to_string(),
+        ));
+    }
+    Ok(())
 }
 
 #[derive(Debug, Error)]
 pub enum ApiKeyError {
     #[error("the client bundle request failed: {0}")]
     RequestError(#[from] reqwest::Error),
-    #[error("the client bundle request failed with status code: {0}")]
-    ResponseError(reqwest::StatusCode),
+    #[error(
+        "the client bundle request failed with status code: {status}{}",
+        body_suffix(body)
+    )]
+    ResponseError {
+        status: reqwest::StatusCode,
+        /// The response body, truncated to a few KB.
+        body: String,
+    },
     #[error("the client bundle response body could not be read: {0}")]
     ResponseBodyError(#[source] reqwest::Error),
     #[error("the API key could not be found in the client bundle")]
     ApiKeyNotFound,
+    #[error("the value found in the client bundle doesn't look like an API key: {0}")]
+    InvalidFormat(String),
+    #[error("invalid API key pattern: {0}")]
+    PatternError(#[from] regex::Error),
+    #[error("the API key cache {path:?} could not be written: {source}")]
+    CacheWriteError {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use httpmock::prelude::*;
+    use serde_json::json;
+    use tempfile::NamedTempFile;
 
     const FAKE_API_KEY: &str = "fake-api-key";
 
@@ -108,6 +556,29 @@ mod tests {
         api_key_mock.assert();
     }
 
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn create_blocking() {
+        // Arrange
+        let server = MockServer::start();
+        let api_key_mock = server.mock(|when, then| {
+            when.method(GET).path("/");
+            then.status(200).body(format!(
+                r#"thingthing;gatewaySubscriptionKey:Q("{}");3fjhkasfd78r3"#,
+                FAKE_API_KEY
+            ));
+        });
+        let url = server.url("/");
+        let client = reqwest::blocking::Client::new();
+
+        // Act
+        let api_key = ApiKey::get_custom_blocking(&client, Some(&url));
+
+        // Assert
+        assert_eq!(api_key.unwrap().get(), FAKE_API_KEY);
+        api_key_mock.assert();
+    }
+
     #[tokio::test]
     async fn create_bad_status() {
         // Arrange
@@ -128,7 +599,7 @@ mod tests {
         assert!(api_key.is_err());
         assert!(matches!(
             api_key.unwrap_err(),
-            ApiKeyError::ResponseError(_)
+            ApiKeyError::ResponseError { .. }
         ));
         api_key_mock.assert();
     }
@@ -154,4 +625,373 @@ mod tests {
         assert!(matches!(api_key.unwrap_err(), ApiKeyError::ApiKeyNotFound));
         api_key_mock.assert();
     }
+
+    async fn get_from_bundle(
+        bundle: &str,
+        patterns: Option<&[&str]>,
+    ) -> Result<ApiKey, ApiKeyError> {
+        let server = MockServer::start_async().await;
+        server
+            .mock_async(|when, then| {
+                when.method(GET).path("/");
+                then.status(200).body(bundle);
+            })
+            .await;
+        let url = server.url("/");
+        let client = reqwest::Client::new();
+        match patterns {
+            Some(patterns) => ApiKey::get_custom_with_patterns(&client, Some(&url), patterns).await,
+            None => ApiKey::get_custom(&client, Some(&url)).await,
+        }
+    }
+
+    #[tokio::test]
+    async fn create_renamed_wrapper() {
+        // Act
+        let api_key = get_from_bundle(
+            &format!(r#"a;gatewaySubscriptionKey:$e("{}");b"#, FAKE_API_KEY),
+            None,
+        )
+        .await;
+
+        // Assert
+        assert_eq!(api_key.unwrap().get(), FAKE_API_KEY);
+    }
+
+    #[tokio::test]
+    async fn create_bare_string() {
+        // Act
+        let api_key = get_from_bundle(
+            &format!(r#"a;gatewaySubscriptionKey:"{}",b"#, FAKE_API_KEY),
+            None,
+        )
+        .await;
+
+        // Assert
+        assert_eq!(api_key.unwrap().get(), FAKE_API_KEY);
+    }
+
+    #[tokio::test]
+    async fn create_with_custom_patterns() {
+        // Act
+        let api_key = get_from_bundle(
+            &format!(r#"a;subKey='{}';b"#, FAKE_API_KEY),
+            Some(&[r"nope:(\w+)", r"subKey='([a-zA-Z0-9-]+)'"]),
+        )
+        .await;
+
+        // Assert
+        assert_eq!(api_key.unwrap().get(), FAKE_API_KEY);
+    }
+
+    #[tokio::test]
+    async fn create_rejects_implausible_keys() {
+        // Act
+        let too_short = get_from_bundle(r#"a;gatewaySubscriptionKey:Q("abc");b"#, None).await;
+        let bad_chars = get_from_bundle(
+            &format!(r#"a;subKey='{}_x';b"#, FAKE_API_KEY),
+            Some(&[r"subKey='([^']+)'"]),
+        )
+        .await;
+
+        // Assert
+        let error = too_short.unwrap_err();
+        assert!(matches!(error, ApiKeyError::InvalidFormat(_)));
+        assert_eq!(
+            error.to_string(),
+            "the value found in the client bundle doesn't look like an API key: 3 characters long, expected 12 to 64"
+        );
+        assert!(matches!(
+            bad_chars.unwrap_err(),
+            ApiKeyError::InvalidFormat(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn create_with_invalid_pattern() {
+        // Act
+        let api_key = get_from_bundle("a;b", Some(&["("])).await;
+
+        // Assert
+        assert!(matches!(api_key.unwrap_err(), ApiKeyError::PatternError(_)));
+    }
+
+    #[tokio::test]
+    async fn cached_key_used_within_ttl() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        let api_key_mock = server
+            .mock_async(|when, then| {
+                when.method(GET).path("/");
+                then.status(500);
+            })
+            .await;
+        let url = server.url("/");
+        let client = reqwest::Client::new();
+        let fetched_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let cache = NamedTempFile::new().unwrap();
+        std::fs::write(
+            cache.path(),
+            json!({"key": "cached-key", "fetched_at": fetched_at}).to_string(),
+        )
+        .unwrap();
+
+        // Act
+        let api_key =
+            ApiKey::get_cached(&client, Some(&url), cache.path(), Duration::from_secs(60)).await;
+
+        // Assert
+        assert_eq!(api_key.unwrap().get(), "cached-key");
+        api_key_mock.assert_hits(0);
+    }
+
+    #[tokio::test]
+    async fn expired_cache_is_refreshed() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        let api_key_mock = server
+            .mock_async(|when, then| {
+                when.method(GET).path("/");
+                then.status(200).body(format!(
+                    r#"thingthing;gatewaySubscriptionKey:Q("{}");3fjhkasfd78r3"#,
+                    FAKE_API_KEY
+                ));
+            })
+            .await;
+        let url = server.url("/");
+        let client = reqwest::Client::new();
+        let cache = NamedTempFile::new().unwrap();
+        std::fs::write(
+            cache.path(),
+            json!({"key": "stale-key", "fetched_at": 0}).to_string(),
+        )
+        .unwrap();
+
+        // Act
+        let api_key =
+            ApiKey::get_cached(&client, Some(&url), cache.path(), Duration::from_secs(60)).await;
+
+        // Assert
+        assert_eq!(api_key.unwrap().get(), FAKE_API_KEY);
+        let cached: CachedKey =
+            serde_json::from_str(&std::fs::read_to_string(cache.path()).unwrap()).unwrap();
+        assert_eq!(cached.key, FAKE_API_KEY);
+        assert!(cached.fetched_at > 0);
+        api_key_mock.assert();
+    }
+
+    #[tokio::test]
+    async fn if_modified_sends_validators() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        let not_modified_mock = server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/")
+                    .header("if-none-match", "\"v1\"")
+                    .header("if-modified-since", "Wed, 21 Oct 2015 07:28:00 GMT");
+                then.status(304);
+            })
+            .await;
+        let url = server.url("/");
+        let client = reqwest::Client::new();
+        let cache = ApiKeyCache {
+            etag: Some("\"v1\"".to_string()),
+            last_modified: Some("Wed, 21 Oct 2015 07:28:00 GMT".to_string()),
+        };
+
+        // Act
+        let fetch = ApiKey::get_custom_if_modified(&client, Some(&url), &cache).await;
+
+        // Assert
+        assert_eq!(fetch.unwrap(), ApiKeyFetch::NotModified);
+        not_modified_mock.assert();
+    }
+
+    #[tokio::test]
+    async fn if_modified_returns_new_key_and_validators() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        server
+            .mock_async(|when, then| {
+                when.method(GET).path("/");
+                then.status(200).header("etag", "\"v2\"").body(format!(
+                    r#"thingthing;gatewaySubscriptionKey:Q("{}");3fjhkasfd78r3"#,
+                    FAKE_API_KEY
+                ));
+            })
+            .await;
+        let url = server.url("/");
+        let client = reqwest::Client::new();
+        let cache = ApiKeyCache {
+            etag: Some("\"v1\"".to_string()),
+            last_modified: None,
+        };
+
+        // Act
+        let fetch = ApiKey::get_custom_if_modified(&client, Some(&url), &cache).await;
+
+        // Assert
+        assert_eq!(
+            fetch.unwrap(),
+            ApiKeyFetch::Fetched {
+                key: ApiKey::from_raw(FAKE_API_KEY),
+                cache: ApiKeyCache {
+                    etag: Some("\"v2\"".to_string()),
+                    last_modified: None,
+                },
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn expired_cache_revalidated_with_etag() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        let api_key_mock = server
+            .mock_async(|when, then| {
+                when.method(GET).path("/").header("if-none-match", "\"v1\"");
+                then.status(304);
+            })
+            .await;
+        let url = server.url("/");
+        let client = reqwest::Client::new();
+        let cache = NamedTempFile::new().unwrap();
+        std::fs::write(
+            cache.path(),
+            json!({"key": "cached-key", "fetched_at": 0, "etag": "\"v1\""}).to_string(),
+        )
+        .unwrap();
+
+        // Act
+        let api_key =
+            ApiKey::get_cached(&client, Some(&url), cache.path(), Duration::from_secs(60)).await;
+
+        // Assert
+        assert_eq!(api_key.unwrap().get(), "cached-key");
+        let cached: CachedKey =
+            serde_json::from_str(&std::fs::read_to_string(cache.path()).unwrap()).unwrap();
+        assert_eq!(cached.key, "cached-key");
+        assert_eq!(cached.validators.etag.as_deref(), Some("\"v1\""));
+        assert!(cached.fetched_at > 0);
+        api_key_mock.assert();
+    }
+
+    #[test]
+    fn from_file_reads_trimmed_key() {
+        // Arrange
+        let key_file = NamedTempFile::new().unwrap();
+        std::fs::write(key_file.path(), "file-key\n").unwrap();
+        let empty_file = NamedTempFile::new().unwrap();
+        std::fs::write(empty_file.path(), " \n").unwrap();
+
+        // Act
+        let key = ApiKey::from_file(key_file.path());
+        let empty = ApiKey::from_file(empty_file.path());
+        let missing = ApiKey::from_file(key_file.path().with_extension("missing"));
+
+        // Assert
+        assert_eq!(key.unwrap().get(), "file-key");
+        assert_eq!(empty.unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert_eq!(missing.unwrap_err().kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn from_env_reads_trimmed_key() {
+        // Arrange
+        // Names unique to this test, since tests share the process environment
+        std::env::set_var("PEPPER_API_TEST_KEY_SET", " env-key\n");
+        std::env::set_var("PEPPER_API_TEST_KEY_EMPTY", "  ");
+
+        // Act
+        let key = ApiKey::from_env("PEPPER_API_TEST_KEY_SET");
+        let empty = ApiKey::from_env("PEPPER_API_TEST_KEY_EMPTY");
+        let unset = ApiKey::from_env("PEPPER_API_TEST_KEY_UNSET");
+
+        // Assert
+        assert_eq!(key.unwrap().get(), "env-key");
+        assert_eq!(empty, None);
+        assert_eq!(unset, None);
+    }
+
+    #[test]
+    fn debug_and_display_redact_key() {
+        // Arrange
+        let key = ApiKey::from_raw("0123456789abcdef");
+        let short_key = ApiKey::from_raw("abc12345");
+
+        // Act
+        let debug = format!("{key:?}");
+        let display = key.to_string();
+        let pool = format!(
+            "{:?}",
+            ApiKeyPool::new(vec![key.clone(), short_key.clone()])
+        );
+
+        // Assert
+        assert_eq!(debug, "ApiKey(***cdef)");
+        assert_eq!(display, "***cdef");
+        assert_eq!(short_key.to_string(), "***redacted***");
+        assert_eq!(ApiKey::unauthenticated().to_string(), "unauthenticated");
+        assert!(!pool.contains("0123456789") && !pool.contains("abc12345"));
+        assert_eq!(key.get(), "0123456789abcdef");
+    }
+
+    #[cfg(feature = "serialize-api-key")]
+    #[test]
+    fn serialize_full_key() {
+        // Act
+        let serialized =
+            serde_json::to_value([ApiKey::from_raw("key-a"), ApiKey::unauthenticated()]);
+
+        // Assert
+        assert_eq!(serialized.unwrap(), json!(["key-a", null]));
+    }
+
+    #[test]
+    fn pool_rotates_keys() {
+        // Arrange
+        let pool = ApiKeyPool::new(vec![ApiKey::from_raw("key-a"), ApiKey::from_raw("key-b")]);
+
+        // Act
+        let keys: Vec<_> = (0..4).map(|_| pool.next_key().unwrap()).collect();
+
+        // Assert
+        let keys: Vec<_> = keys.iter().map(ApiKey::get).collect();
+        assert_eq!(keys, ["key-a", "key-b", "key-a", "key-b"]);
+    }
+
+    #[test]
+    fn pool_remove_drops_key() {
+        // Arrange
+        let pool = ApiKeyPool::new(vec![ApiKey::from_raw("key-a"), ApiKey::from_raw("key-b")]);
+
+        // Act
+        pool.remove(&ApiKey::from_raw("key-a"));
+
+        // Assert
+        assert_eq!(pool.len(), 1);
+        assert_eq!(pool.next_key().unwrap().get(), "key-b");
+        assert_eq!(pool.next_key().unwrap().get(), "key-b");
+    }
+
+    #[test]
+    fn pool_keeps_last_key() {
+        // Arrange
+        let pool = ApiKeyPool::new(vec![ApiKey::from_raw("key-a"), ApiKey::from_raw("key-b")]);
+
+        // Act
+        let removed_first = pool.remove_unless_last(&ApiKey::from_raw("key-a"));
+        let removed_again = pool.remove_unless_last(&ApiKey::from_raw("key-a"));
+        let removed_last = pool.remove_unless_last(&ApiKey::from_raw("key-b"));
+
+        // Assert
+        assert!(removed_first);
+        assert!(removed_again);
+        assert!(!removed_last);
+        assert_eq!(pool.len(), 1);
+    }
 }
@@ -0,0 +1,1431 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use futures::{future, stream, Stream, StreamExt, TryStreamExt};
+use reqwest::StatusCode;
+
+use crate::{
+    constants::{DEFAULT_MAX_ATTEMPTS, DEFAULT_RETRY_BASE_DELAY},
+    error::{ClientBuildError, GetError},
+    locations::{Location, LocationFilter, LocationQuery, Locations},
+    menu::{Endpoint, FullMenu, Menu, MenuOptions},
+    metrics::{Metrics, MetricsSnapshot},
+    util::{default_http_client, wait_for_rate_limit, with_retries, RateLimiter},
+    ApiKey, ApiKeyPool, LocationId,
+};
+
+/// An API key and endpoints bundled together, so callers don't have to thread them through every call.
+#[derive(Clone, Debug)]
+pub struct Client {
+    backend: Backend,
+    metrics: Arc<Metrics>,
+}
+
+#[derive(Clone, Debug)]
+enum Backend {
+    Http {
+        /// Shared with every clone, so a dropped or refreshed key applies to all of them.
+        keys: ApiKeyPool,
+        http: reqwest::Client,
+        locations_endpoint: Option<String>,
+        menu_endpoint: Endpoint,
+        rate_limiter: Option<RateLimiter>,
+        key_refresh: Option<KeyRefresh>,
+    },
+    Fixtures {
+        locations: Arc<Vec<Location>>,
+        menus: Arc<HashMap<LocationId, Menu>>,
+    },
+}
+
+impl Client {
+    /// Create a client for the default Chipotle endpoints.
+    pub fn new(key: ApiKey) -> Self {
+        Self::custom(key, default_http_client(), None, None)
+    }
+
+    /// Start building a client. Only the API key is required.
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::default()
+    }
+
+    /// Create a client with a custom HTTP client and endpoints. `None` uses the default endpoint.
+    /// The menu endpoint must use the `$store` replace token; use [`Client::builder`] for other tokens.
+    pub fn custom(
+        key: ApiKey,
+        http: reqwest::Client,
+        locations_endpoint: Option<&str>,
+        menu_endpoint: Option<&str>,
+    ) -> Self {
+        Self {
+            backend: Backend::Http {
+                keys: ApiKeyPool::new(vec![key]),
+                http,
+                locations_endpoint: locations_endpoint.map(str::to_string),
+                menu_endpoint: Endpoint::with_default_token(menu_endpoint),
+                rate_limiter: None,
+                key_refresh: None,
+            },
+            metrics: Arc::default(),
+        }
+    }
+
+    /// Create a client that serves the given locations and menus instead of making requests, for testing.
+    /// Getting the menu of a store without one fails with a 404 [`GetError::ResponseError`].
+    pub fn from_fixtures(locations: Vec<Location>, menus: HashMap<LocationId, Menu>) -> Self {
+        Self {
+            backend: Backend::Fixtures {
+                locations: Arc::new(locations),
+                menus: Arc::new(menus),
+            },
+            metrics: Arc::default(),
+        }
+    }
+
+    /// Counters for the requests made so far by this client and all of its clones.
+    /// A client created with [`Client::from_fixtures`] makes no requests, so its counters stay at zero.
+    pub fn metrics(&self) -> MetricsSnapshot {
+        self.metrics.snapshot()
+    }
+
+    /// Retrieve all US locations.
+    pub async fn get_all_locations(&self) -> Result<Locations, GetError> {
+        match &self.backend {
+            Backend::Http { .. } => {
+                let locations: Vec<Location> = self.locations_stream().try_collect().await?;
+                Ok(Locations::from(locations).dedup_sorted())
+            }
+            Backend::Fixtures { locations, .. } => Ok(locations.to_vec().into()),
+        }
+    }
+
+    /// Stream all US locations as each page of them arrives.
+    /// Unlike [`Client::get_all_locations`] they aren't sorted, and the stream ends after the first error.
+    pub fn locations_stream(&self) -> impl Stream<Item = Result<Location, GetError>> + '_ {
+        match &self.backend {
+            Backend::Http {
+                http,
+                locations_endpoint,
+                ..
+            } => {
+                let endpoint = locations_endpoint.as_deref();
+                Locations::stream_us_pages(
+                    &LocationQuery::default(),
+                    move |page_index| async move {
+                        let query = &LocationQuery::default();
+                        self.execute_with_recovery(|key| async move {
+                            Locations::get_page(
+                                &key,
+                                http,
+                                endpoint,
+                                query,
+                                page_index,
+                                &self.metrics,
+                            )
+                            .await
+                        })
+                        .await
+                    },
+                )
+                .boxed()
+            }
+            Backend::Fixtures { locations, .. } => stream::iter(locations.to_vec()).map(Ok).boxed(),
+        }
+    }
+
+    /// Retrieve all US locations, then stream their menus, getting up to `concurrency` at once.
+    /// Menus come in the order they finish. Only failing to get the locations is an error here.
+    pub async fn menus_stream(
+        &self,
+        concurrency: usize,
+    ) -> Result<impl Stream<Item = (Location, Result<Menu, GetError>)> + '_, GetError> {
+        let locations: Vec<Location> = self.get_all_locations().await?.into();
+        Ok(stream::iter(locations)
+            .map(move |location| async move {
+                let menu = self.get_menu(&location.id).await;
+                (location, menu)
+            })
+            .buffer_unordered(concurrency.max(1)))
+    }
+
+    /// Stream the menus of the US locations passing `filter`, getting up to `concurrency` at once.
+    /// Each menu request starts as soon as its location arrives, rather than after every page of
+    /// locations is in, so targeted queries finish sooner. Menus come in the order they finish.
+    /// Failing to get locations is yielded as an error, after which the stream ends once the
+    /// menus already started are in.
+    pub fn filtered_menus_stream(
+        &self,
+        filter: LocationFilter,
+        concurrency: usize,
+    ) -> impl Stream<Item = Result<(Location, Result<Menu, GetError>), GetError>> + '_ {
+        self.locations_stream()
+            .try_filter(move |location| future::ready(filter.matches(location)))
+            .map(move |location| async move {
+                let location = location?;
+                let menu = self.get_menu(&location.id).await;
+                Ok((location, menu))
+            })
+            .buffer_unordered(concurrency.max(1))
+    }
+
+    /// Get the summarized menu for a restaurant. Errors name the restaurant; see [`GetError::root`] for the cause.
+    pub async fn get_menu(&self, restaurant_id: &LocationId) -> Result<Menu, GetError> {
+        let menu = match &self.backend {
+            Backend::Http {
+                http,
+                menu_endpoint,
+                ..
+            } => {
+                let options = &MenuOptions::default();
+                self.execute_with_recovery(|key| async move {
+                    Menu::get_with_metrics(
+                        restaurant_id,
+                        &key,
+                        http,
+                        menu_endpoint,
+                        options,
+                        &self.metrics,
+                    )
+                    .await
+                })
+                .await
+            }
+            Backend::Fixtures { menus, .. } => {
+                menus
+                    .get(restaurant_id)
+                    .cloned()
+                    .ok_or_else(|| GetError::ResponseError {
+                        status: reqwest::StatusCode::NOT_FOUND,
+                        body: String::new(),
+                    })
+            }
+        };
+        menu.map_err(|e| e.with_context(*restaurant_id))
+    }
+
+    /// Get a restaurant's summarized menu and its raw item list from a single request.
+    /// Fixture clients only hold summaries, so for them this fails as if the menu were missing.
+    pub async fn get_menu_full(&self, restaurant_id: &LocationId) -> Result<FullMenu, GetError> {
+        let menu = match &self.backend {
+            Backend::Http {
+                http,
+                menu_endpoint,
+                ..
+            } => {
+                let options = &MenuOptions::default();
+                self.execute_with_recovery(|key| async move {
+                    Menu::get_full_with_metrics(
+                        restaurant_id,
+                        &key,
+                        http,
+                        menu_endpoint,
+                        options,
+                        &self.metrics,
+                    )
+                    .await
+                })
+                .await
+            }
+            Backend::Fixtures { .. } => Err(GetError::ResponseError {
+                status: reqwest::StatusCode::NOT_FOUND,
+                body: String::new(),
+            }),
+        };
+        menu.map_err(|e| e.with_context(*restaurant_id))
+    }
+
+    /// Retrieve all US locations, then their menus in batches of `concurrency`, waiting `delay` between batches.
+    /// Results are in location order. A failed menu doesn't stop the crawl; only failing to get the locations does.
+    /// See [`Client::crawl_menus`] to crawl chosen locations and handle each batch as it finishes.
+    pub async fn get_all_menus(
+        &self,
+        concurrency: usize,
+        delay: Duration,
+    ) -> Result<Vec<(Location, Result<Menu, GetError>)>, GetError> {
+        self.get_all_menus_with_progress(concurrency, delay, |_| {})
+            .await
+    }
+
+    /// Like [`Client::get_all_menus`], calling `on_progress` as soon as each store's menu request finishes,
+    /// so a slow store doesn't hold back reports for the rest of its batch.
+    pub async fn get_all_menus_with_progress<F>(
+        &self,
+        concurrency: usize,
+        delay: Duration,
+        on_progress: F,
+    ) -> Result<Vec<(Location, Result<Menu, GetError>)>, GetError>
+    where
+        F: FnMut(CrawlProgress),
+    {
+        let locations: Vec<Location> = self.get_all_locations().await?.into();
+        Ok(self
+            .crawl_menus(locations, concurrency, delay, on_progress)
+            .flat_map(stream::iter)
+            .map(|crawled| (crawled.location, crawled.menu))
+            .collect()
+            .await)
+    }
+
+    /// Get the menus of `locations` in batches of `concurrency`, waiting `delay` between batches,
+    /// and yield each batch in location order once all of its menus are in.
+    /// `on_progress` is called as soon as each store's menu request finishes, so a slow store
+    /// doesn't hold back reports for the rest of its batch.
+    pub fn crawl_menus<'a, F>(
+        &'a self,
+        locations: Vec<Location>,
+        concurrency: usize,
+        delay: Duration,
+        on_progress: F,
+    ) -> impl Stream<Item = Vec<CrawledMenu>> + 'a
+    where
+        F: FnMut(CrawlProgress) + 'a,
+    {
+        let concurrency = concurrency.max(1);
+        let total = locations.len();
+        let remaining = locations.into_iter().peekable();
+        stream::unfold(
+            (remaining, on_progress, 0, false),
+            move |(mut remaining, mut on_progress, mut completed, started)| async move {
+                remaining.peek()?;
+                if started {
+                    tokio::time::sleep(delay).await;
+                }
+                let batch: Vec<Location> = remaining.by_ref().take(concurrency).collect();
+                let mut menus = stream::iter(batch.iter().enumerate())
+                    .map(|(index, location)| async move {
+                        let started = Instant::now();
+                        let menu = self.get_menu(&location.id).await;
+                        (index, menu, started.elapsed())
+                    })
+                    .buffer_unordered(concurrency);
+                let mut finished = Vec::with_capacity(batch.len());
+                while let Some((index, menu, latency)) = menus.next().await {
+                    completed += 1;
+                    on_progress(CrawlProgress {
+                        completed,
+                        total,
+                        last_error: menu.as_ref().err(),
+                    });
+                    finished.push((index, menu, latency));
+                }
+                // The stream borrows the batch, which is moved into the results next
+                drop(menus);
+                finished.sort_by_key(|(index, ..)| *index);
+                let crawled = batch
+                    .into_iter()
+                    .zip(finished)
+                    .map(|(location, (_, menu, latency))| CrawledMenu {
+                        location,
+                        menu,
+                        latency,
+                    })
+                    .collect();
+                Some((crawled, (remaining, on_progress, completed, true)))
+            },
+        )
+    }
+
+    /// Send a request, passing it the next API key, with the client's resilience applied:
+    /// every attempt waits for the rate limiter, retryable failures are retried with backoff, and
+    /// a key rejected with a 403 is dropped and the request tried with the next one. The last key
+    /// is kept; with key refresh enabled, it is replaced with a fresh one and tried once more.
+    /// Every read method goes through here.
+    async fn execute_with_recovery<T, F, Fut>(&self, mut request: F) -> Result<T, GetError>
+    where
+        F: FnMut(ApiKey) -> Fut,
+        Fut: Future<Output = Result<T, GetError>>,
+    {
+        let Backend::Http {
+            keys,
+            http,
+            rate_limiter,
+            key_refresh,
+            ..
+        } = &self.backend
+        else {
+            unreachable!("a client with fixtures makes no requests");
+        };
+        let mut refreshed = false;
+        loop {
+            let current_key = keys
+                .next_key()
+                .ok_or_else(|| GetError::BuildError("no API keys available".to_string()))?;
+            let mut attempt = 0;
+            let result = with_retries(DEFAULT_MAX_ATTEMPTS, DEFAULT_RETRY_BASE_DELAY, || {
+                attempt += 1;
+                if attempt > 1 {
+                    self.metrics.record_retry();
+                }
+                let response = request(current_key.clone());
+                async move {
+                    wait_for_rate_limit(rate_limiter.as_ref()).await;
+                    response.await
+                }
+            })
+            .await;
+            let rejected = matches!(&result, Err(e) if e.status() == Some(StatusCode::FORBIDDEN));
+            if !rejected {
+                return result;
+            }
+            // Rotate past a rejected key while others are left, then fall back to refreshing the last
+            if keys.remove_unless_last(&current_key) {
+                continue;
+            }
+            match key_refresh {
+                Some(refresh) if !refreshed => {
+                    refreshed = true;
+                    if !refresh.refresh(keys, &current_key, http).await {
+                        return result;
+                    }
+                }
+                _ => return result,
+            }
+        }
+    }
+}
+
+/// Where a [`Client`] scrapes a new API key from once its current one is rejected.
+#[derive(Clone, Debug)]
+struct KeyRefresh {
+    bundle_endpoint: Option<String>,
+}
+
+impl KeyRefresh {
+    /// Replace the `rejected` key, unless another request already has. Returns whether there's a new key to try.
+    async fn refresh(&self, keys: &ApiKeyPool, rejected: &ApiKey, http: &reqwest::Client) -> bool {
+        if !keys.contains(rejected) {
+            return true;
+        }
+        match ApiKey::get_custom(http, self.bundle_endpoint.as_deref()).await {
+            Ok(fresh) => {
+                tracing::info!("refreshed the API key after it was rejected");
+                keys.replace(rejected, fresh);
+                true
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "unable to refresh the rejected API key");
+                false
+            }
+        }
+    }
+}
+
+/// Progress through a [`Client::crawl_menus`] or [`Client::get_all_menus_with_progress`] crawl, reported as each store finishes.
+#[derive(Debug)]
+pub struct CrawlProgress<'a> {
+    /// Stores whose menu request has finished, successfully or not.
+    pub completed: usize,
+    /// Stores in the crawl.
+    pub total: usize,
+    /// Why the store that just finished failed, if it did.
+    pub last_error: Option<&'a GetError>,
+}
+
+/// A store's menu from [`Client::crawl_menus`], with how long its request took, retries included.
+#[derive(Debug)]
+pub struct CrawledMenu {
+    pub location: Location,
+    pub menu: Result<Menu, GetError>,
+    pub latency: Duration,
+}
+
+/// Builds a [`Client`], validating the endpoints and settings on [`ClientBuilder::build`].
+#[derive(Debug, Default)]
+pub struct ClientBuilder {
+    keys: Option<ApiKeyPool>,
+    http: Option<reqwest::Client>,
+    restaurant_endpoint: Option<String>,
+    menu_endpoint: Option<String>,
+    rate_limit: Option<f64>,
+    key_refresh: Option<KeyRefresh>,
+}
+
+impl ClientBuilder {
+    pub fn api_key(&mut self, key: ApiKey) -> &mut Self {
+        self.keys = Some(ApiKeyPool::new(vec![key]));
+        self
+    }
+
+    /// Spread requests across a pool of API keys instead of a single key.
+    /// A key rejected with a 403 is dropped from the pool, as long as another key is left.
+    pub fn api_key_pool(&mut self, keys: ApiKeyPool) -> &mut Self {
+        self.keys = Some(keys);
+        self
+    }
+
+    /// The HTTP client to send requests with. Defaults to one with compression and a request timeout.
+    pub fn http_client(&mut self, http: reqwest::Client) -> &mut Self {
+        self.http = Some(http);
+        self
+    }
+
+    /// The restaurant service URL that locations are fetched from.
+    pub fn restaurant_endpoint(&mut self, url: impl Into<String>) -> &mut Self {
+        self.restaurant_endpoint = Some(url.into());
+        self
+    }
+
+    /// The menu service URL template. Its `$`-prefixed replace token (e.g. `$store`) is detected on build.
+    pub fn menu_endpoint(&mut self, url: impl Into<String>) -> &mut Self {
+        self.menu_endpoint = Some(url.into());
+        self
+    }
+
+    /// Send at most `requests_per_second` requests, shared with every clone of the client.
+    /// It must be a positive, finite number, or [`ClientBuilder::build`] fails.
+    pub fn rate_limit(&mut self, requests_per_second: f64) -> &mut Self {
+        self.rate_limit = Some(requests_per_second);
+        self
+    }
+
+    /// When a request is rejected with a 403, scrape a new API key from the client bundle at
+    /// `bundle_endpoint` (the default bundle if `None`) and try the request again once.
+    pub fn refresh_api_key(&mut self, bundle_endpoint: Option<&str>) -> &mut Self {
+        self.key_refresh = Some(KeyRefresh {
+            bundle_endpoint: bundle_endpoint.map(str::to_string),
+        });
+        self
+    }
+
+    pub fn build(&self) -> Result<Client, ClientBuildError> {
+        let keys = self
+            .keys
+            .clone()
+            .filter(|keys| !keys.is_empty())
+            .ok_or(ClientBuildError::MissingApiKey)?;
+        let menu_endpoint = match &self.menu_endpoint {
+            Some(url) => Endpoint::from_template(url)?,
+            None => Endpoint::with_default_token(None),
+        };
+        let rate_limiter = match self.rate_limit {
+            Some(rate) if !(rate.is_finite() && rate > 0.0) => {
+                return Err(ClientBuildError::InvalidRateLimit(rate))
+            }
+            rate => rate.map(RateLimiter::new),
+        };
+        Ok(Client {
+            backend: Backend::Http {
+                keys,
+                http: self.http.clone().unwrap_or_else(default_http_client),
+                locations_endpoint: self.restaurant_endpoint.clone(),
+                menu_endpoint,
+                rate_limiter,
+                key_refresh: self.key_refresh.clone(),
+            },
+            metrics: Arc::default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::API_KEY_HEADER;
+    use httpmock::prelude::*;
+    use serde_json::{json, Value};
+
+    fn location(id: i32) -> Location {
+        Location {
+            id: LocationId(id),
+            zip_code: "12345".parse().unwrap(),
+            ..Default::default()
+        }
+    }
+
+    /// A location as the restaurant service returns it.
+    fn raw_location(id: i32) -> Value {
+        json!({
+            "restaurantNumber": id,
+            "addresses": [{"postalCode": "12345", "countryCode": "US"}]
+        })
+    }
+
+    /// Stores 1, 2 and 3, with menus for 1 and 3 only.
+    fn fixture_client() -> Client {
+        Client::from_fixtures(
+            vec![location(1), location(2), location(3)],
+            HashMap::from([
+                (LocationId(1), Menu::default()),
+                (LocationId(3), Menu::default()),
+            ]),
+        )
+    }
+
+    #[tokio::test]
+    async fn get_all_menus_keeps_going_after_failures() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        server
+            .mock_async(|when, then| {
+                when.method(POST).path("/locations");
+                then.status(200).json_body(
+                    json!({"data": [raw_location(1), raw_location(2), raw_location(3)]}),
+                );
+            })
+            .await;
+        let menu_mock = server
+            .mock_async(|when, then| {
+                when.path_matches(Regex::new("^/menu/(1|3)$").unwrap());
+                then.status(200).json_body(json!({
+                    "restaurantId": 1,
+                    "entrees": [{
+                        "itemCategory": "Entree",
+                        "itemType": "Bowl",
+                        "itemId": "1",
+                        "itemName": "Chicken Bowl",
+                        "unitPrice": 8.99,
+                        "unitDeliveryPrice": 9.99
+                    }],
+                    "sides": []
+                }));
+            })
+            .await;
+        let missing_mock = server
+            .mock_async(|when, then| {
+                when.path("/menu/2");
+                then.status(404);
+            })
+            .await;
+        let client = Client::custom(
+            ApiKey::from_raw("fake_api_key"),
+            reqwest::Client::new(),
+            Some(&server.url("/locations")),
+            Some(&server.url("/menu/$store")),
+        );
+
+        // Act
+        let results = client.get_all_menus(2, Duration::ZERO).await;
+
+        // Assert
+        let results = results.unwrap();
+        let ids: Vec<i32> = results.iter().map(|(location, _)| location.id.0).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+        assert!(results[0].1.is_ok());
+        let error = results[1].1.as_ref().unwrap_err();
+        assert_eq!(error.restaurant_id(), Some(LocationId(2)));
+        assert!(matches!(
+            error.root(),
+            GetError::ResponseError {
+                status: reqwest::StatusCode::NOT_FOUND,
+                ..
+            }
+        ));
+        assert!(results[2].1.is_ok());
+        menu_mock.assert_hits(2);
+        missing_mock.assert();
+    }
+
+    #[tokio::test]
+    async fn get_menu_full_makes_one_request() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        let menu_mock = server
+            .mock_async(|when, then| {
+                when.path("/menu/1");
+                then.status(200).json_body(json!({
+                    "restaurantId": 1,
+                    "entrees": [
+                        {
+                            "itemCategory": "Entree",
+                            "itemType": "Bowl",
+                            "itemId": "1",
+                            "itemName": "Chicken Bowl",
+                            "unitPrice": 8.99,
+                            "unitDeliveryPrice": 9.99
+                        },
+                        {
+                            "itemCategory": "Entree",
+                            "itemType": "Burrito",
+                            "itemId": "2",
+                            "itemName": "Chicken Burrito",
+                            "unitPrice": 8.99,
+                            "unitDeliveryPrice": 9.99
+                        }
+                    ],
+                    "sides": [{
+                        "itemCategory": "Side",
+                        "itemType": "Side",
+                        "itemId": "3",
+                        "itemName": "Chips",
+                        "unitPrice": 1.99,
+                        "unitDeliveryPrice": 2.49
+                    }]
+                }));
+            })
+            .await;
+        let client = Client::custom(
+            ApiKey::from_raw("fake_api_key"),
+            reqwest::Client::new(),
+            None,
+            Some(&server.url("/menu/$store")),
+        );
+
+        // Act
+        let menu = client.get_menu_full(&LocationId(1)).await.unwrap();
+
+        // Assert
+        assert!(menu.summary.bowl_price("chicken").is_some());
+        let items: Vec<&str> = menu
+            .response
+            .entrees
+            .iter()
+            .chain(&menu.response.sides)
+            .map(|item| item.item_name.as_str())
+            .collect();
+        assert_eq!(items, ["Chicken Bowl", "Chicken Burrito", "Chips"]);
+        menu_mock.assert_hits(1);
+    }
+
+    #[tokio::test]
+    async fn metrics_shared_between_clones() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        let body = json!({
+            "restaurantId": 1,
+            "entrees": [{
+                "itemCategory": "Entree",
+                "itemType": "Bowl",
+                "itemId": "1",
+                "itemName": "Chicken Bowl",
+                "unitPrice": 8.99,
+                "unitDeliveryPrice": 9.99
+            }],
+            "sides": []
+        })
+        .to_string();
+        server
+            .mock_async(|when, then| {
+                when.path("/menu/1");
+                then.status(200).body(&body);
+            })
+            .await;
+        server
+            .mock_async(|when, then| {
+                when.path("/menu/2");
+                then.status(404);
+            })
+            .await;
+        let unavailable_mock = server
+            .mock_async(|when, then| {
+                when.path("/menu/3");
+                then.status(503);
+            })
+            .await;
+        let client = Client::builder()
+            .api_key(ApiKey::from_raw("fake_api_key"))
+            .http_client(reqwest::Client::new())
+            .menu_endpoint(server.url("/menu/$store"))
+            .build()
+            .unwrap();
+        let clone = client.clone();
+
+        // Act
+        let _ = client.get_menu(&LocationId(1)).await;
+        let _ = clone.get_menu(&LocationId(2)).await;
+        let _ = clone.get_menu(&LocationId(3)).await;
+
+        // Assert
+        let metrics = client.metrics();
+        assert_eq!(metrics, clone.metrics());
+        assert_eq!(metrics.requests, 5);
+        assert_eq!(metrics.failures, 4);
+        assert_eq!(metrics.retries, 2);
+        assert_eq!(metrics.bytes_downloaded, body.len() as u64);
+        assert_eq!(
+            metrics.statuses,
+            std::collections::BTreeMap::from([(200, 1), (404, 1), (503, 3)])
+        );
+        unavailable_mock.assert_hits(3);
+    }
+
+    #[tokio::test]
+    async fn rate_limit_shared_between_clones() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        let menu_mock = server
+            .mock_async(|when, then| {
+                when.path("/menu/1");
+                then.status(404);
+            })
+            .await;
+        let client = Client::builder()
+            .api_key(ApiKey::from_raw("fake_api_key"))
+            .menu_endpoint(server.url("/menu/$store"))
+            .rate_limit(10.0)
+            .build()
+            .unwrap();
+        let clone = client.clone();
+        let started = std::time::Instant::now();
+
+        // Act
+        for client in [&client, &clone, &client] {
+            let _ = client.get_menu(&LocationId(1)).await;
+        }
+
+        // Assert
+        assert!(started.elapsed() >= Duration::from_millis(200));
+        menu_mock.assert_hits(3);
+    }
+
+    #[tokio::test]
+    async fn rate_limit_applies_to_each_location_page() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        let full_page: Vec<_> = (1..=4000).map(raw_location).collect();
+        let first_page_mock = server
+            .mock_async(|when, then| {
+                when.path("/locations")
+                    .json_body_partial(r#"{"pageIndex": 0}"#);
+                then.status(200).json_body(json!({ "data": full_page }));
+            })
+            .await;
+        let second_page_mock = server
+            .mock_async(|when, then| {
+                when.path("/locations")
+                    .json_body_partial(r#"{"pageIndex": 1}"#);
+                then.status(200).json_body(json!({ "data": [] }));
+            })
+            .await;
+        let client = Client::builder()
+            .api_key(ApiKey::from_raw("fake_api_key"))
+            .restaurant_endpoint(server.url("/locations"))
+            .rate_limit(5.0)
+            .build()
+            .unwrap();
+        let started = std::time::Instant::now();
+
+        // Act
+        let locations = client.get_all_locations().await;
+
+        // Assert
+        assert_eq!(locations.unwrap().len(), 4000);
+        assert!(started.elapsed() >= Duration::from_millis(200));
+        first_page_mock.assert();
+        second_page_mock.assert();
+    }
+
+    #[tokio::test]
+    async fn rate_limit_applies_to_each_retry() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        let unavailable_mock = server
+            .mock_async(|when, then| {
+                when.path("/menu/1");
+                then.status(503);
+            })
+            .await;
+        let client = Client::builder()
+            .api_key(ApiKey::from_raw("fake_api_key"))
+            .menu_endpoint(server.url("/menu/$store"))
+            .rate_limit(0.8)
+            .build()
+            .unwrap();
+        let started = std::time::Instant::now();
+
+        // Act
+        let menu = client.get_menu(&LocationId(1)).await;
+
+        // Assert
+        assert!(menu.is_err());
+        // Three attempts are 1.25s apart; the retry delays alone add up to at most 2.25s
+        assert!(started.elapsed() >= Duration::from_millis(2500));
+        unavailable_mock.assert_hits(3);
+    }
+
+    /// A client whose locations and menus both come from `server`, which answers everything with a 503.
+    async fn unavailable_client(server: &MockServer) -> (Client, httpmock::Mock<'_>) {
+        let mock = server
+            .mock_async(|_, then| {
+                then.status(503);
+            })
+            .await;
+        let client = Client::custom(
+            ApiKey::from_raw("fake_api_key"),
+            reqwest::Client::new(),
+            Some(&server.url("/locations")),
+            Some(&server.url("/menu/$store")),
+        );
+        (client, mock)
+    }
+
+    #[tokio::test]
+    async fn every_read_method_retries() {
+        // Arrange
+        let servers = [
+            MockServer::start_async().await,
+            MockServer::start_async().await,
+            MockServer::start_async().await,
+            MockServer::start_async().await,
+            MockServer::start_async().await,
+            MockServer::start_async().await,
+        ];
+        let mut clients = Vec::new();
+        let mut mocks = Vec::new();
+        for server in &servers {
+            let (client, mock) = unavailable_client(server).await;
+            clients.push(client);
+            mocks.push(mock);
+        }
+
+        // Act
+        let (all_locations, locations, menu, all_menus, menus, filtered_menus) = tokio::join!(
+            clients[0].get_all_locations(),
+            clients[1].locations_stream().collect::<Vec<_>>(),
+            clients[2].get_menu(&LocationId(1)),
+            clients[3].get_all_menus(5, Duration::ZERO),
+            clients[4].menus_stream(5),
+            clients[5]
+                .filtered_menus_stream(LocationFilter::new(), 5)
+                .collect::<Vec<_>>(),
+        );
+
+        // Assert
+        assert!(all_locations.is_err());
+        assert!(matches!(locations.as_slice(), [Err(_)]));
+        assert!(menu.is_err());
+        assert!(all_menus.is_err());
+        assert!(menus.is_err());
+        assert!(matches!(filtered_menus.as_slice(), [Err(_)]));
+        for (mock, client) in mocks.iter().zip(&clients) {
+            mock.assert_hits(3);
+            assert_eq!(client.metrics().retries, 2);
+        }
+    }
+
+    #[tokio::test]
+    async fn refreshes_rejected_api_key() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        let bundle_mock = server
+            .mock_async(|when, then| {
+                when.method(GET).path("/bundle.js");
+                then.status(200)
+                    .body(r#"a;gatewaySubscriptionKey:"fresh-api-key",b"#);
+            })
+            .await;
+        let rejected_mock = server
+            .mock_async(|when, then| {
+                when.path("/menu/1").header(API_KEY_HEADER, "stale-api-key");
+                then.status(403);
+            })
+            .await;
+        let accepted_mock = server
+            .mock_async(|when, then| {
+                when.path("/menu/1").header(API_KEY_HEADER, "fresh-api-key");
+                then.status(200).json_body(json!({
+                    "restaurantId": 1,
+                    "entrees": [{
+                        "itemCategory": "Entree",
+                        "itemType": "Bowl",
+                        "itemId": "1",
+                        "itemName": "Chicken Bowl",
+                        "unitPrice": 8.99,
+                        "unitDeliveryPrice": 9.99
+                    }],
+                    "sides": []
+                }));
+            })
+            .await;
+        let client = Client::builder()
+            .api_key(ApiKey::from_raw("stale-api-key"))
+            .menu_endpoint(server.url("/menu/$store"))
+            .refresh_api_key(Some(&server.url("/bundle.js")))
+            .build()
+            .unwrap();
+
+        // Act
+        let menu = client.get_menu(&LocationId(1)).await;
+        let again = client.get_menu(&LocationId(1)).await;
+
+        // Assert
+        assert!(menu.is_ok(), "{:?}", menu.unwrap_err());
+        assert!(again.is_ok());
+        bundle_mock.assert();
+        rejected_mock.assert();
+        accepted_mock.assert_hits(2);
+    }
+
+    #[tokio::test]
+    async fn rejected_api_key_without_refresh() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        let bundle_mock = server
+            .mock_async(|when, then| {
+                when.path("/bundle.js");
+                then.status(200)
+                    .body(r#"a;gatewaySubscriptionKey:"fresh-api-key",b"#);
+            })
+            .await;
+        let rejected_mock = server
+            .mock_async(|when, then| {
+                when.path("/menu/1");
+                then.status(403);
+            })
+            .await;
+        let client = Client::custom(
+            ApiKey::from_raw("stale-api-key"),
+            reqwest::Client::new(),
+            None,
+            Some(&server.url("/menu/$store")),
+        );
+
+        // Act
+        let menu = client.get_menu(&LocationId(1)).await;
+
+        // Assert
+        assert_eq!(
+            menu.unwrap_err().status(),
+            Some(reqwest::StatusCode::FORBIDDEN)
+        );
+        rejected_mock.assert();
+        bundle_mock.assert_hits(0);
+    }
+
+    #[tokio::test]
+    async fn builder_infers_replace_token() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        let menu_mock = server
+            .mock_async(|when, then| {
+                when.path("/menus/1234");
+                then.status(404);
+            })
+            .await;
+
+        // Act
+        let client = Client::builder()
+            .api_key(ApiKey::from_raw("fake_api_key"))
+            .http_client(reqwest::Client::new())
+            .menu_endpoint(server.url("/menus/$store_id"))
+            .build();
+
+        // Assert
+        let menu = client.unwrap().get_menu(&LocationId(1234)).await;
+        assert!(matches!(
+            menu.unwrap_err().root(),
+            GetError::ResponseError { .. }
+        ));
+        menu_mock.assert();
+    }
+
+    #[test]
+    fn builder_validates() {
+        // Act
+        let missing_key = Client::builder().build();
+        let empty_pool = Client::builder()
+            .api_key_pool(ApiKeyPool::new(Vec::new()))
+            .build();
+        let bad_template = Client::builder()
+            .api_key(ApiKey::unauthenticated())
+            .menu_endpoint("https://example.com/menus")
+            .build();
+        let bad_rate_limits = [0.0, -1.0, f64::NAN, f64::INFINITY].map(|rate| {
+            Client::builder()
+                .api_key(ApiKey::unauthenticated())
+                .rate_limit(rate)
+                .build()
+        });
+
+        // Assert
+        assert!(matches!(
+            missing_key.unwrap_err(),
+            ClientBuildError::MissingApiKey
+        ));
+        assert!(matches!(
+            empty_pool.unwrap_err(),
+            ClientBuildError::MissingApiKey
+        ));
+        assert!(matches!(
+            bad_template.unwrap_err(),
+            ClientBuildError::InvalidMenuEndpoint(_)
+        ));
+        for bad_rate_limit in bad_rate_limits {
+            assert!(matches!(
+                bad_rate_limit.unwrap_err(),
+                ClientBuildError::InvalidRateLimit(_)
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn get_all_menus_location_failure() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        server
+            .mock_async(|when, then| {
+                when.path("/locations");
+                then.status(403);
+            })
+            .await;
+        let client = Client::custom(
+            ApiKey::from_raw("fake_api_key"),
+            reqwest::Client::new(),
+            Some(&server.url("/locations")),
+            None,
+        );
+
+        // Act
+        let results = client.get_all_menus(5, Duration::ZERO).await;
+
+        // Assert
+        assert!(matches!(
+            results.unwrap_err(),
+            GetError::ResponseError { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn from_fixtures_serves_canned_data() {
+        // Arrange
+        let client = fixture_client();
+
+        // Act
+        let results = client.get_all_menus(5, Duration::ZERO).await;
+
+        // Assert
+        let results = results.unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].0, location(1));
+        assert_eq!(results[0].1.as_ref().unwrap(), &Menu::default());
+        let error = results[1].1.as_ref().unwrap_err();
+        assert_eq!(error.restaurant_id(), Some(LocationId(2)));
+        assert!(matches!(
+            error.root(),
+            GetError::ResponseError {
+                status: reqwest::StatusCode::NOT_FOUND,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn get_all_menus_reports_progress() {
+        // Arrange
+        let client = fixture_client();
+        let mut reports = Vec::new();
+
+        // Act
+        let results = client
+            .get_all_menus_with_progress(2, Duration::ZERO, |progress| {
+                reports.push((
+                    progress.completed,
+                    progress.total,
+                    progress.last_error.is_some(),
+                ))
+            })
+            .await;
+
+        // Assert
+        assert_eq!(results.unwrap().len(), 3);
+        assert_eq!(reports, vec![(1, 3, false), (2, 3, true), (3, 3, false)]);
+    }
+
+    #[tokio::test]
+    async fn crawl_menus_yields_each_batch_in_order() {
+        // Arrange
+        let client = fixture_client();
+        let locations = vec![location(3), location(2), location(1)];
+        let mut completed = Vec::new();
+
+        // Act
+        let batches: Vec<Vec<CrawledMenu>> = client
+            .crawl_menus(locations, 2, Duration::ZERO, |progress| {
+                completed.push(progress.completed)
+            })
+            .collect()
+            .await;
+
+        // Assert
+        let batches: Vec<Vec<(i32, bool)>> = batches
+            .iter()
+            .map(|batch| {
+                batch
+                    .iter()
+                    .map(|crawled| (crawled.location.id.0, crawled.menu.is_ok()))
+                    .collect()
+            })
+            .collect();
+        assert_eq!(batches, [vec![(3, true), (2, false)], vec![(1, true)]]);
+        assert_eq!(completed, [1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn api_key_pool_rotates_past_rejected_keys() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        let rejected_mock = server
+            .mock_async(|when, then| {
+                when.path("/menu/1").header(API_KEY_HEADER, "revoked-key");
+                then.status(403);
+            })
+            .await;
+        let accepted_mock = server
+            .mock_async(|when, then| {
+                when.path("/menu/1").header(API_KEY_HEADER, "working-key");
+                then.status(200).json_body(json!({
+                    "restaurantId": 1,
+                    "entrees": [{
+                        "itemCategory": "Entree",
+                        "itemType": "Bowl",
+                        "itemId": "1",
+                        "itemName": "Chicken Bowl",
+                        "unitPrice": 8.99,
+                        "unitDeliveryPrice": 9.99
+                    }],
+                    "sides": []
+                }));
+            })
+            .await;
+        let pool = ApiKeyPool::new(vec![
+            ApiKey::from_raw("revoked-key"),
+            ApiKey::from_raw("working-key"),
+        ]);
+        let client = Client::builder()
+            .api_key_pool(pool.clone())
+            .menu_endpoint(server.url("/menu/$store"))
+            .build()
+            .unwrap();
+
+        // Act
+        let menus = [
+            client.get_menu(&LocationId(1)).await,
+            client.get_menu(&LocationId(1)).await,
+            client.get_menu(&LocationId(1)).await,
+        ];
+
+        // Assert
+        assert!(menus.iter().all(Result::is_ok));
+        rejected_mock.assert();
+        accepted_mock.assert_hits(3);
+        assert_eq!(pool.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn get_all_menus_reports_progress_as_stores_finish() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        server
+            .mock_async(|when, then| {
+                when.path("/locations");
+                then.status(200)
+                    .json_body(json!({"data": [raw_location(1), raw_location(2)]}));
+            })
+            .await;
+        server
+            .mock_async(|when, then| {
+                when.path("/menu/1");
+                then.status(404).delay(Duration::from_millis(500));
+            })
+            .await;
+        server
+            .mock_async(|when, then| {
+                when.path("/menu/2");
+                then.status(200).json_body(json!({
+                    "restaurantId": 2,
+                    "entrees": [{
+                        "itemCategory": "Entree",
+                        "itemType": "Bowl",
+                        "itemId": "1",
+                        "itemName": "Chicken Bowl",
+                        "unitPrice": 8.99,
+                        "unitDeliveryPrice": 9.99
+                    }],
+                    "sides": []
+                }));
+            })
+            .await;
+        let client = Client::custom(
+            ApiKey::from_raw("fake_api_key"),
+            reqwest::Client::new(),
+            Some(&server.url("/locations")),
+            Some(&server.url("/menu/$store")),
+        );
+        let mut reports = Vec::new();
+
+        // Act
+        let results = client
+            .get_all_menus_with_progress(2, Duration::ZERO, |progress| {
+                reports.push((progress.completed, progress.last_error.is_some()))
+            })
+            .await;
+
+        // Assert
+        assert_eq!(reports, vec![(1, false), (2, true)]);
+        let results = results.unwrap();
+        let ids: Vec<i32> = results.iter().map(|(location, _)| location.id.0).collect();
+        assert_eq!(ids, vec![1, 2]);
+        assert!(results[0].1.is_err());
+        assert!(results[1].1.is_ok());
+    }
+
+    #[tokio::test]
+    async fn menus_stream_yields_every_store() {
+        // Arrange
+        let client = fixture_client();
+
+        // Act
+        let stream = client.menus_stream(2).await.unwrap();
+        let mut results: Vec<_> = stream
+            .map(|(location, menu)| (location.id.0, menu.is_ok()))
+            .collect()
+            .await;
+
+        // Assert
+        results.sort();
+        assert_eq!(results, vec![(1, true), (2, false), (3, true)]);
+    }
+
+    #[tokio::test]
+    async fn locations_stream_ends_after_error() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        let locations_mock = server
+            .mock_async(|when, then| {
+                when.path("/locations");
+                then.status(400);
+            })
+            .await;
+        let client = Client::custom(
+            ApiKey::from_raw("fake_api_key"),
+            reqwest::Client::new(),
+            Some(&server.url("/locations")),
+            None,
+        );
+
+        // Act
+        let results: Vec<_> = client.locations_stream().collect().await;
+
+        // Assert
+        assert_eq!(results.len(), 1);
+        assert!(matches!(
+            results[0],
+            Err(GetError::ResponseError {
+                status: reqwest::StatusCode::BAD_REQUEST,
+                ..
+            })
+        ));
+        locations_mock.assert();
+    }
+
+    #[tokio::test]
+    async fn filtered_menus_stream_only_fetches_matching_stores() {
+        // Arrange
+        let in_zip = |id: i32, zip_code: &str| Location {
+            zip_code: zip_code.parse().unwrap(),
+            ..location(id)
+        };
+        let client = Client::from_fixtures(
+            vec![in_zip(1, "10001"), in_zip(2, "94103"), in_zip(3, "10001")],
+            HashMap::from([
+                (LocationId(1), Menu::default()),
+                (LocationId(2), Menu::default()),
+            ]),
+        );
+
+        // Act
+        let mut results: Vec<_> = client
+            .filtered_menus_stream(LocationFilter::new().zip("10001").unwrap(), 2)
+            .map(|result| {
+                let (location, menu) = result.unwrap();
+                (location.id.0, menu.is_ok())
+            })
+            .collect()
+            .await;
+
+        // Assert
+        results.sort();
+        assert_eq!(results, vec![(1, true), (3, false)]);
+    }
+
+    #[tokio::test]
+    async fn filtered_menus_stream_starts_menus_before_the_last_page() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        let mut full_page: Vec<_> = (1..=4000).map(raw_location).collect();
+        full_page[0]["addresses"][0]["postalCode"] = json!("10001");
+        server
+            .mock_async(|when, then| {
+                when.path("/locations")
+                    .json_body_partial(r#"{"pageIndex": 0}"#);
+                then.status(200).json_body(json!({ "data": full_page }));
+            })
+            .await;
+        server
+            .mock_async(|when, then| {
+                when.path("/locations")
+                    .json_body_partial(r#"{"pageIndex": 1}"#);
+                then.status(200)
+                    .delay(Duration::from_secs(2))
+                    .json_body(json!({ "data": [] }));
+            })
+            .await;
+        let menu_mock = server
+            .mock_async(|when, then| {
+                when.path("/menu/1");
+                then.status(200).json_body(json!({
+                    "restaurantId": 1,
+                    "entrees": [{
+                        "itemCategory": "Entree",
+                        "itemType": "Bowl",
+                        "itemId": "1",
+                        "itemName": "Chicken Bowl",
+                        "unitPrice": 8.99,
+                        "unitDeliveryPrice": 9.99
+                    }],
+                    "sides": []
+                }));
+            })
+            .await;
+        let client = Client::builder()
+            .api_key(ApiKey::from_raw("fake_api_key"))
+            .restaurant_endpoint(server.url("/locations"))
+            .menu_endpoint(server.url("/menu/$store"))
+            .build()
+            .unwrap();
+        let started = std::time::Instant::now();
+
+        // Act
+        let mut menus =
+            client.filtered_menus_stream(LocationFilter::new().zip("10001").unwrap(), 5);
+        let first = menus.next().await;
+
+        // Assert
+        assert!(started.elapsed() < Duration::from_secs(2));
+        let (location, menu) = first.unwrap().unwrap();
+        assert_eq!(location.id, LocationId(1));
+        assert!(menu.is_ok());
+        menu_mock.assert();
+    }
+
+    #[tokio::test]
+    async fn filtered_menus_stream_reports_location_failure() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        server
+            .mock_async(|when, then| {
+                when.path("/locations");
+                then.status(403);
+            })
+            .await;
+        let client = Client::custom(
+            ApiKey::from_raw("fake_api_key"),
+            reqwest::Client::new(),
+            Some(&server.url("/locations")),
+            None,
+        );
+
+        // Act
+        let results: Vec<_> = client
+            .filtered_menus_stream(LocationFilter::new(), 5)
+            .collect()
+            .await;
+
+        // Assert
+        assert_eq!(results.len(), 1);
+        assert!(matches!(results[0], Err(GetError::ResponseError { .. })));
+    }
+}
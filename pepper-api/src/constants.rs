@@ -1,7 +1,28 @@
+use std::time::Duration;
+
 /// The default endpoint format for the Chipotle menu service
 pub const DEFAULT_MENU_SERVICE_URL_FORMAT: &str = "https://services.chipotle.com/menuinnovation/v1/restaurants/$store_id/onlinemenu?channelId=web&includeUnavailableItems=true";
 pub const DEFAULT_MENU_SERVICE_URL_REPLACE_TOKEN: &str = "$store_id";
 
+/// The default number of attempts made for a request that fails with a retryable error
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+/// The default delay before the first retry, doubled on each subsequent retry
+pub const DEFAULT_RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+/// The longest delay before any retry, however many attempts came before it
+pub const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// The default timeout for a whole request, from connecting to reading the body
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The default limit on the size of a response body, past which reading it fails rather than buffering more
+pub const DEFAULT_MAX_BODY_BYTES: usize = 16 * 1024 * 1024;
+
+/// The User-Agent sent by the default HTTP clients, matching a desktop browser since the API is web-facing
+pub const DEFAULT_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36";
+
+/// The environment variable [`crate::ApiKey::from_default_env`] reads the API key from
+pub const API_KEY_ENV_VAR: &str = "CHIPOTLE_API_KEY";
+
 /// The header to use to send API keys in requests
 pub const API_KEY_HEADER: &str = "Ocp-Apim-Subscription-Key";
 
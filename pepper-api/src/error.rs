@@ -1,34 +1,291 @@
-use crate::*;
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+use std::path::PathBuf;
 use thiserror::Error;
 
+use crate::LocationId;
+
 #[derive(Debug, Error)]
 pub enum GetError {
     #[error("unable to build the request: {0}")]
     BuildError(String),
     #[error("the request failed: {0}")]
-    RequestError(#[from] reqwest::Error),
-    #[error("the request failed with status code: {0}")]
-    ResponseError(reqwest::StatusCode),
+    RequestError(#[source] reqwest::Error),
+    #[error("the request timed out: {0}")]
+    Timeout(#[source] reqwest::Error),
+    #[error("the request failed with status code: {status}{}", body_suffix(body))]
+    ResponseError {
+        status: reqwest::StatusCode,
+        /// The response body, truncated to a few KB. Often explains why the request was rejected.
+        body: String,
+    },
     #[error("the response body could not be read: {0}")]
     ResponseBodyError(#[source] reqwest::Error),
+    #[error("the response body is larger than the limit of {limit} bytes")]
+    BodyTooLarge { limit: usize },
+    /// A successful response that isn't JSON, usually an HTML interstitial or captcha page from a CDN blocking the request.
+    #[error("expected a JSON response but got {content_type}; the request may have been blocked")]
+    UnexpectedContentType { content_type: String },
     #[error("unable to parse the response body: {0}")]
     ParseError(#[from] serde_json::Error),
     #[error("unable to translate response object: {0}")]
-    TranslateError(#[from] menu::MenuBuilderError),
+    TranslateError(String),
+    #[error("invalid price for {0}: {1}")]
+    InvalidPriceError(String, f32),
+    #[error("unable to get the menu for restaurant {restaurant_id}: {source}")]
+    Menu {
+        restaurant_id: LocationId,
+        #[source]
+        source: Box<GetError>,
+    },
+}
+
+/// Appended to status code errors so the response body shows up in the message when there is one.
+pub(crate) fn body_suffix(body: &str) -> String {
+    if body.is_empty() {
+        String::new()
+    } else {
+        format!(": {body}")
+    }
+}
+
+/// Timeouts get their own variant so they can be told apart from other transport errors.
+impl From<reqwest::Error> for GetError {
+    fn from(error: reqwest::Error) -> Self {
+        if error.is_timeout() {
+            GetError::Timeout(error)
+        } else {
+            GetError::RequestError(error)
+        }
+    }
+}
+
+impl GetError {
+    /// Note which restaurant's menu failed. An error that already names a restaurant is left as is.
+    pub fn with_context(self, restaurant_id: LocationId) -> Self {
+        match self {
+            GetError::Menu { .. } => self,
+            source => GetError::Menu {
+                restaurant_id,
+                source: Box::new(source),
+            },
+        }
+    }
+
+    /// The restaurant whose menu failed, if it was added with [`GetError::with_context`].
+    pub fn restaurant_id(&self) -> Option<LocationId> {
+        match self {
+            GetError::Menu { restaurant_id, .. } => Some(*restaurant_id),
+            _ => None,
+        }
+    }
+
+    /// The underlying error, without any restaurant context.
+    pub fn root(&self) -> &GetError {
+        match self {
+            GetError::Menu { source, .. } => source.root(),
+            _ => self,
+        }
+    }
+
+    /// Short machine-readable classification of the error.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            GetError::BuildError(_) => "build",
+            GetError::RequestError(_) => "request",
+            GetError::Timeout(_) => "timeout",
+            GetError::ResponseError { .. } => "response",
+            GetError::ResponseBodyError(_) => "response_body",
+            GetError::BodyTooLarge { .. } => "body_too_large",
+            GetError::UnexpectedContentType { .. } => "unexpected_content_type",
+            GetError::ParseError(_) => "parse",
+            GetError::TranslateError(_) => "translate",
+            GetError::InvalidPriceError(_, _) => "invalid_price",
+            GetError::Menu { source, .. } => source.kind(),
+        }
+    }
+
+    /// The HTTP status code associated with the error, if there is one.
+    pub fn status(&self) -> Option<reqwest::StatusCode> {
+        match self {
+            GetError::RequestError(e) | GetError::ResponseBodyError(e) => e.status(),
+            GetError::ResponseError { status, .. } => Some(*status),
+            GetError::Menu { source, .. } => source.status(),
+            _ => None,
+        }
+    }
+
+    /// Whether the error was caused by the server failing (5xx) or the request timing out.
+    pub fn is_server_failure(&self) -> bool {
+        match self {
+            GetError::Timeout(_) => true,
+            GetError::Menu { source, .. } => source.is_server_failure(),
+            GetError::ResponseError { status, .. } => status.is_server_error(),
+            _ => false,
+        }
+    }
+
+    /// Whether the request is worth retrying: a server failure or a 429 rate limit.
+    pub fn is_retryable(&self) -> bool {
+        self.is_server_failure()
+            || matches!(
+                self.root(),
+                GetError::ResponseError {
+                    status: reqwest::StatusCode::TOO_MANY_REQUESTS,
+                    ..
+                }
+            )
+    }
+}
+
+/// Serializes as `{"kind": ..., "status": ..., "message": ...}` so failures can be written alongside results.
+impl Serialize for GetError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("GetError", 3)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("status", &self.status().map(|status| status.as_u16()))?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum EndpointError {
+    #[error("no $-prefixed replace token found in the endpoint")]
+    NoReplaceToken,
+    #[error("more than one replace token found in the endpoint: {0}")]
+    MultipleReplaceTokens(String),
+    #[error("the replace token {token} does not appear in the endpoint {url}")]
+    ReplaceTokenNotInEndpoint { token: String, url: String },
+}
+
+#[derive(Debug, Error)]
+pub enum ClientBuildError {
+    #[error("an API key is required")]
+    MissingApiKey,
+    #[error("invalid menu endpoint: {0}")]
+    InvalidMenuEndpoint(#[from] EndpointError),
+    #[error("rate limit must be a positive number of requests per second, got {0}")]
+    InvalidRateLimit(f64),
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ZipCodeError {
+    #[error("no zip code")]
+    Missing,
+    #[error("invalid US zip code: {0:?}")]
+    Invalid(String),
 }
 
 #[derive(Debug, Error)]
 pub enum LoadError {
-    #[error("unable to read the file: {0}")]
-    ReadError(#[from] std::io::Error),
-    #[error("unable to parse the file: {0}")]
-    ParseError(#[from] serde_json::Error),
+    #[error("unable to read the file {}: {source}", path.display())]
+    ReadError {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("unable to parse the file {}: {source}", path.display())]
+    ParseError {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
 }
 
 #[derive(Debug, Error)]
 pub enum SaveError {
-    #[error("unable to write the file: {0}")]
-    WriteError(#[from] std::io::Error),
-    #[error("unable to serialize the data: {0}")]
-    SerializeError(#[from] serde_json::Error),
+    #[error("unable to write the file {}: {source}", path.display())]
+    WriteError {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("unable to serialize the data for {}: {source}", path.display())]
+    SerializeError {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("unable to write CSV: {0}")]
+    CsvError(#[from] csv::Error),
+    #[cfg(feature = "sqlite")]
+    #[error("unable to write to the database: {0}")]
+    DatabaseError(#[from] rusqlite::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{locations, LocationId};
+    use serde_json::json;
+
+    #[test]
+    fn serialize_response_error() {
+        // Arrange
+        let error = GetError::ResponseError {
+            status: reqwest::StatusCode::SERVICE_UNAVAILABLE,
+            body: String::new(),
+        };
+
+        // Act
+        let serialized = serde_json::to_value(&error).unwrap();
+
+        // Assert
+        assert_eq!(
+            serialized,
+            json!({
+                "kind": "response",
+                "status": 503,
+                "message": "the request failed with status code: 503 Service Unavailable"
+            })
+        );
+    }
+
+    #[test]
+    fn with_context_names_restaurant() {
+        // Arrange
+        let error = GetError::ResponseError {
+            status: reqwest::StatusCode::SERVICE_UNAVAILABLE,
+            body: String::new(),
+        };
+
+        // Act
+        let error = error
+            .with_context(LocationId(1234))
+            .with_context(LocationId(5678));
+
+        // Assert
+        assert_eq!(error.restaurant_id(), Some(LocationId(1234)));
+        assert!(error.to_string().contains("restaurant 1234"));
+        assert_eq!(error.kind(), "response");
+        assert_eq!(
+            error.status(),
+            Some(reqwest::StatusCode::SERVICE_UNAVAILABLE)
+        );
+        assert!(error.is_retryable());
+        assert!(matches!(error.root(), GetError::ResponseError { .. }));
+    }
+
+    #[test]
+    fn serialize_failed_location_record() {
+        // Arrange
+        let location = locations::Location {
+            id: LocationId(1234),
+            zip_code: "12345".parse().unwrap(),
+            ..Default::default()
+        };
+        let error = GetError::BuildError("bad template".to_string());
+
+        // Act
+        let record = json!({"location": location, "error": error});
+
+        // Assert
+        assert_eq!(record["location"]["id"], 1234);
+        assert_eq!(record["error"]["kind"], "build");
+        assert!(record["error"]["status"].is_null());
+        assert_eq!(
+            record["error"]["message"],
+            "unable to build the request: bad template"
+        );
+    }
 }
@@ -0,0 +1,203 @@
+use std::io::Write;
+
+use serde::Serialize;
+
+use crate::{
+    error::SaveError,
+    locations::Location,
+    menu::{Menu, Money, BOWL_PROTEINS},
+    LocationId,
+};
+
+/// The CSV header: the location, then normal and delivery prices for every known bowl protein.
+fn menu_csv_header() -> Vec<String> {
+    let mut header = vec!["restaurant_id".to_string(), "zip_code".to_string()];
+    for protein in BOWL_PROTEINS {
+        let column = protein.replace(' ', "_");
+        header.push(format!("{column}_normal"));
+        header.push(format!("{column}_delivery"));
+    }
+    header
+}
+
+/// A single price for one item at one store, for long/tidy output.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct PricePoint {
+    pub id: LocationId,
+    pub item: String,
+    pub channel: &'static str,
+    pub price: Money,
+}
+
+/// Flatten a menu into one price point per item and channel (pickup or delivery).
+pub fn price_points(location: &Location, menu: &Menu) -> Vec<PricePoint> {
+    menu.prices()
+        .into_iter()
+        .flat_map(|(item, price)| {
+            [
+                ("pickup", price.normal_price),
+                ("delivery", price.delivery_price),
+            ]
+            .map(|(channel, price)| PricePoint {
+                id: location.id,
+                item: item.clone(),
+                channel,
+                price,
+            })
+        })
+        .collect()
+}
+
+/// Writes one CSV row per location as menus come in, so large runs don't need to be held in memory.
+pub struct MenuCsvWriter<W: Write> {
+    writer: csv::Writer<W>,
+}
+
+impl<W: Write> MenuCsvWriter<W> {
+    /// Create the writer and write the header row.
+    pub fn new(writer: W) -> Result<Self, SaveError> {
+        let mut writer = csv::Writer::from_writer(writer);
+        writer.write_record(menu_csv_header())?;
+        Ok(Self { writer })
+    }
+
+    /// Write a row for a location. Price cells are left blank if the menu or the protein is missing.
+    pub fn write(&mut self, location: &Location, menu: Option<&Menu>) -> Result<(), SaveError> {
+        let mut record = vec![location.id.to_string(), location.zip_code.to_string()];
+        for protein in BOWL_PROTEINS {
+            match menu.and_then(|menu| menu.bowl_price(protein)) {
+                Some(price) => {
+                    record.push(price.normal_price.as_dollars().to_string());
+                    record.push(price.delivery_price.as_dollars().to_string());
+                }
+                None => record.extend([String::new(), String::new()]),
+            }
+        }
+        self.writer.write_record(&record)?;
+        Ok(())
+    }
+
+    /// Flush written rows to the underlying writer.
+    pub fn flush(&mut self) -> Result<(), SaveError> {
+        self.writer.flush().map_err(csv::Error::from)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::menu::Price;
+    use std::collections::BTreeMap;
+
+    fn fake_menu() -> Menu {
+        let price = |normal_price: f64| Price {
+            normal_price: Money::from_dollars(normal_price),
+            delivery_price: Money::from_dollars(normal_price + 1.0),
+            calories: None,
+        };
+        Menu {
+            bowl_prices: BTreeMap::from([
+                ("veggie".to_string(), price(7.99)),
+                ("chicken".to_string(), price(8.99)),
+                ("steak".to_string(), price(9.99)),
+            ]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn price_points_per_store() {
+        // Arrange
+        let location = Location {
+            id: LocationId(1234),
+            zip_code: "12345".parse().unwrap(),
+            ..Default::default()
+        };
+
+        // Act
+        let points = price_points(&location, &fake_menu());
+
+        // Assert
+        assert_eq!(points.len(), 6);
+        assert!(points.iter().all(|point| point.id == LocationId(1234)));
+        assert_eq!(
+            points[0],
+            PricePoint {
+                id: LocationId(1234),
+                item: "chicken_bowl".to_string(),
+                channel: "pickup",
+                price: Money::from_cents(899),
+            }
+        );
+        assert_eq!(
+            serde_json::to_value(&points[1]).unwrap(),
+            serde_json::json!({"id": 1234, "item": "chicken_bowl", "channel": "delivery", "price": 9.99})
+        );
+    }
+
+    #[test]
+    fn write_rows_incrementally() {
+        // Arrange
+        let mut writer = MenuCsvWriter::new(Vec::new()).unwrap();
+        let location = Location {
+            id: LocationId(1234),
+            zip_code: "12345".parse().unwrap(),
+            ..Default::default()
+        };
+        let menu = fake_menu();
+
+        // Act
+        writer.flush().unwrap();
+        let after_header = writer.writer.get_ref().clone();
+        writer.write(&location, Some(&menu)).unwrap();
+        writer.flush().unwrap();
+        let after_first_row = writer.writer.get_ref().clone();
+
+        // Assert
+        assert_eq!(String::from_utf8(after_header).unwrap().lines().count(), 1);
+        assert_eq!(
+            String::from_utf8(after_first_row).unwrap().lines().count(),
+            2
+        );
+    }
+
+    #[test]
+    fn write_parses_as_csv() {
+        // Arrange
+        let mut writer = MenuCsvWriter::new(Vec::new()).unwrap();
+        let menu = fake_menu();
+
+        // Act
+        writer
+            .write(
+                &Location {
+                    id: LocationId(1234),
+                    zip_code: "12345".parse().unwrap(),
+                    ..Default::default()
+                },
+                Some(&menu),
+            )
+            .unwrap();
+        writer
+            .write(
+                &Location {
+                    id: LocationId(5678),
+                    zip_code: "56789".parse().unwrap(),
+                    ..Default::default()
+                },
+                None,
+            )
+            .unwrap();
+        let output = writer.writer.into_inner().unwrap();
+
+        // Assert
+        let mut reader = csv::Reader::from_reader(output.as_slice());
+        assert_eq!(reader.headers().unwrap(), menu_csv_header().as_slice());
+        let rows: Vec<_> = reader.records().map(Result::unwrap).collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(&rows[0][4], "8.99");
+        assert_eq!(&rows[1][0], "5678");
+        assert_eq!(&rows[1][4], "");
+    }
+}
@@ -1,9 +1,26 @@
 mod api_interfaces;
 mod api_key;
+mod client;
 pub mod constants;
 pub mod error;
+pub mod export;
+mod location_id;
 pub mod locations;
 pub mod menu;
+mod metrics;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
 mod util;
+mod zip_code;
 
-pub use api_key::ApiKey;
+pub use api_key::{ApiKey, ApiKeyCache, ApiKeyFetch, ApiKeyPool};
+pub use client::{Client, ClientBuilder, CrawlProgress, CrawledMenu};
+pub use location_id::LocationId;
+pub use metrics::MetricsSnapshot;
+#[cfg(feature = "blocking")]
+pub use util::default_blocking_http_client;
+pub use util::{
+    default_http_client, default_http_client_builder, default_http_client_with_headers,
+    default_http_client_with_proxy, default_http_client_with_timeout,
+};
+pub use zip_code::ZipCode;
@@ -0,0 +1,57 @@
+use std::{fmt, num::ParseIntError, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+/// A Chipotle restaurant ID. Serializes as the bare integer.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(transparent)]
+pub struct LocationId(pub i32);
+
+impl fmt::Display for LocationId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for LocationId {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Self)
+    }
+}
+
+impl From<i32> for LocationId {
+    fn from(id: i32) -> Self {
+        Self(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serde_transparent() {
+        // Act
+        let serialized = serde_json::to_string(&LocationId(1234)).unwrap();
+        let deserialized: LocationId = serde_json::from_str("1234").unwrap();
+
+        // Assert
+        assert_eq!(serialized, "1234");
+        assert_eq!(deserialized, LocationId(1234));
+    }
+
+    #[test]
+    fn display_and_parse_round_trip() {
+        // Act
+        let parsed: Result<LocationId, _> = LocationId(1234).to_string().parse();
+
+        // Assert
+        assert_eq!(parsed.unwrap(), LocationId(1234));
+        assert!("not-an-id".parse::<LocationId>().is_err());
+    }
+}
@@ -1,11 +1,28 @@
-use crate::{api_interfaces::locations, util::default_http_client, ApiKey};
+use crate::{
+    api_interfaces::locations,
+    constants::{DEFAULT_MAX_ATTEMPTS, DEFAULT_MAX_BODY_BYTES, DEFAULT_RETRY_BASE_DELAY},
+    metrics::Metrics,
+    util::{default_http_client, error_body, read_body, retry_transient_io, with_retries},
+    ApiKey, LocationId, ZipCode,
+};
 
-use super::constants::API_KEY_HEADER;
 use super::error::*;
-use reqwest::Client;
-use serde::{self, Deserialize, Serialize};
+use derive_builder::Builder;
+use futures::{stream, Stream, TryStreamExt};
+use reqwest::{Client, StatusCode};
+use serde::{self, de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::{json, Value};
-use std::{collections::HashMap, path::Path, sync::LazyLock};
+use std::{
+    collections::{HashMap, HashSet},
+    future::Future,
+    path::Path,
+    sync::LazyLock,
+    time::Duration,
+};
+use tracing::Instrument;
+
+#[cfg(feature = "blocking")]
+use crate::util::{error_body_blocking, read_body_blocking, with_retries_blocking};
 
 const DEFAULT_LOCATION_INFO_ENDPOINT: &str =
     "https://services.chipotle.com/restaurant/v3/restaurant/";
@@ -14,42 +31,156 @@ const DEFAULT_LOCATION_INFO_ENDPOINT: &str =
 static ZIP_CODE_OVERRIDES: LazyLock<HashMap<i32, &'static str>> =
     LazyLock::new(|| HashMap::from([(3065, "75235")]));
 
-/// Default request body for getting all locations.
-static DEFAULT_REQUEST_BODY: LazyLock<Value> = LazyLock::new(|| {
-    json!({
-        "latitude": 0,
-        "longitude": 0,
-        "radius": 999999999,
-        "restaurantStatuses": ["OPEN", "LAB"],
-        "conceptIds": ["CMG"],
-        "orderBy": "distance",
-        "orderByDescending": false,
-        // 4000 is a good upper limit for the number of locations. Change when there are more.
-        "pageSize": 4000,
-        "pageIndex": 0,
-        "embeds": {
-            "addressTypes": ["MAIN"],
-            "realHours": false,
-            "directions": false,
-            "catering": false,
-            "onlineOrdering": true,
-            "timezone": false,
-            "marketing": false,
-            "chipotlane": false,
-            "sustainability": false,
-            "experience": false,
-        },
-    })
-});
+const METERS_PER_MILE: f64 = 1609.344;
+
+/// Mean radius of the Earth, used for distances between locations.
+const EARTH_RADIUS_MILES: f64 = 3958.8;
+
+/// Country filter used by the US-only getters.
+const US_ONLY: &[&str] = &["US"];
+
+/// Most pages fetched for one query, in case the service keeps returning full pages.
+const MAX_LOCATION_PAGES: u32 = 100;
+
+const LOCATION_CSV_HEADER: [&str; 6] = ["id", "zip_code", "name", "street", "city", "state"];
+
+/// Parameters used to build the location query request body.
+#[derive(Builder, Clone, Debug, PartialEq)]
+#[builder(default)]
+pub struct LocationQuery {
+    /// Latitude of the search center.
+    pub latitude: f64,
+    /// Longitude of the search center.
+    pub longitude: f64,
+    /// Search radius around the center, in meters.
+    pub radius: u64,
+    /// Restaurant concepts to include. Chipotle is `CMG`.
+    #[builder(setter(into))]
+    pub concept_ids: Vec<String>,
+    /// Number of locations requested per page.
+    pub page_size: u32,
+    /// Statuses of the restaurants to include, e.g. `CLOSED` to include closed restaurants.
+    #[builder(setter(into))]
+    pub restaurant_statuses: Vec<String>,
+    /// Extra details requested for each restaurant.
+    pub embeds: LocationEmbeds,
+}
+
+/// Which extra details the restaurant service includes with each location.
+/// Main addresses are always requested, since zip codes come from them.
+#[derive(Builder, Clone, Debug, PartialEq, Eq)]
+#[builder(default)]
+pub struct LocationEmbeds {
+    pub real_hours: bool,
+    pub directions: bool,
+    pub catering: bool,
+    pub online_ordering: bool,
+    pub timezone: bool,
+    pub marketing: bool,
+    pub chipotlane: bool,
+    pub sustainability: bool,
+    pub experience: bool,
+}
+
+impl Default for LocationEmbeds {
+    /// Only online ordering details.
+    fn default() -> Self {
+        Self {
+            real_hours: false,
+            directions: false,
+            catering: false,
+            online_ordering: true,
+            timezone: false,
+            marketing: false,
+            chipotlane: false,
+            sustainability: false,
+            experience: false,
+        }
+    }
+}
+
+impl Default for LocationQuery {
+    /// Searches from (0, 0) with a radius large enough to cover every open or lab location.
+    fn default() -> Self {
+        Self {
+            latitude: 0.0,
+            longitude: 0.0,
+            radius: 999999999,
+            concept_ids: vec!["CMG".to_string()],
+            page_size: 4000,
+            restaurant_statuses: vec!["OPEN".to_string(), "LAB".to_string()],
+            embeds: LocationEmbeds::default(),
+        }
+    }
+}
+
+impl LocationQuery {
+    fn to_request_body(&self, page_index: u32) -> Value {
+        json!({
+            "latitude": self.latitude,
+            "longitude": self.longitude,
+            "radius": self.radius,
+            "restaurantStatuses": self.restaurant_statuses,
+            "conceptIds": self.concept_ids,
+            "orderBy": "distance",
+            "orderByDescending": false,
+            "pageSize": self.page_size,
+            "pageIndex": page_index,
+            "embeds": {
+                "addressTypes": ["MAIN"],
+                "realHours": self.embeds.real_hours,
+                "directions": self.embeds.directions,
+                "catering": self.embeds.catering,
+                "onlineOrdering": self.embeds.online_ordering,
+                "timezone": self.embeds.timezone,
+                "marketing": self.embeds.marketing,
+                "chipotlane": self.embeds.chipotlane,
+                "sustainability": self.embeds.sustainability,
+                "experience": self.embeds.experience,
+            },
+        })
+    }
+}
 
 /// Key identifying information for the location.
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Location {
-    pub id: i32,
-    pub zip_code: String,
+    pub id: LocationId,
+    pub zip_code: ZipCode,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub street: Option<String>,
+    #[serde(default)]
+    pub city: Option<String>,
+    #[serde(default)]
+    pub state: Option<String>,
+    #[serde(default)]
+    pub lat: Option<f64>,
+    #[serde(default)]
+    pub lon: Option<f64>,
+}
+
+impl Location {
+    /// The zip code for this location with any matching override applied.
+    pub fn effective_zip(&self, overrides: &HashMap<LocationId, ZipCode>) -> ZipCode {
+        overrides.get(&self.id).unwrap_or(&self.zip_code).clone()
+    }
+}
+
+/// The zip code overrides applied when parsing locations from the API.
+pub fn default_zip_code_overrides() -> HashMap<LocationId, ZipCode> {
+    ZIP_CODE_OVERRIDES
+        .iter()
+        .map(|(id, zip_code)| {
+            let zip_code = zip_code.parse().expect("zip code overrides are valid");
+            (LocationId(*id), zip_code)
+        })
+        .collect()
 }
 
-#[derive(Debug, PartialEq, Eq, Serialize)]
+#[derive(Debug, PartialEq, Serialize)]
 pub struct Locations(Vec<Location>);
 
 impl Locations {
@@ -59,67 +190,688 @@ impl Locations {
         Self::get_all_us_custom(key, &client, None).await
     }
 
-    /// Retrieve all US locations using a custom HTTP client and endpoint.
+    /// Retrieve all US locations using a custom HTTP client and endpoint, sorted by ID.
     pub async fn get_all_us_custom(
         key: &ApiKey,
         client: &Client,
         endpoint: Option<&str>,
     ) -> Result<Self, GetError> {
-        let response = client
-            .post(endpoint.unwrap_or(DEFAULT_LOCATION_INFO_ENDPOINT))
-            .header("Content-Type", "application/json")
-            .header(API_KEY_HEADER, key.get())
-            .body(DEFAULT_REQUEST_BODY.to_string())
-            .send()
+        Self::get_all_custom(key, client, endpoint, US_ONLY)
+            .await
+            .map(Self::dedup_sorted)
+    }
+
+    /// Retrieve all US locations sorted by ID, making up to `max_attempts` attempts with exponential backoff from `base_delay`.
+    pub async fn get_all_us_custom_with_retries(
+        key: &ApiKey,
+        client: &Client,
+        endpoint: Option<&str>,
+        max_attempts: u32,
+        base_delay: Duration,
+    ) -> Result<Self, GetError> {
+        let query = LocationQuery::default();
+        let pages = Self::get_pages(
+            key,
+            client,
+            endpoint,
+            &query,
+            max_attempts,
+            base_delay,
+            &Metrics::default(),
+        )
+        .await?;
+        Ok(pages.finish(US_ONLY).dedup_sorted())
+    }
+
+    /// Stream all US locations matching `query` page by page, getting each page with `get_page`
+    /// given its index. `get_page` is responsible for any retries.
+    /// Locations come in the order the service returns them, and the stream ends after the first error.
+    pub(crate) fn stream_us_pages<'a, F, Fut>(
+        query: &LocationQuery,
+        get_page: F,
+    ) -> impl Stream<Item = Result<Location, GetError>> + 'a
+    where
+        F: FnMut(u32) -> Fut + 'a,
+        Fut: Future<Output = Result<locations::Response, GetError>> + 'a,
+    {
+        let pages = Pages::new(query);
+        stream::try_unfold(
+            (get_page, pages, true),
+            |(mut get_page, mut pages, more)| async move {
+                if !more {
+                    return Ok::<_, GetError>(None);
+                }
+                let page = get_page(pages.next_index).await?;
+                let more = pages.push(page);
+                let locations = pages.take(US_ONLY);
+                Ok(Some((
+                    stream::iter(locations.into_iter().map(Ok)),
+                    (get_page, pages, more),
+                )))
+            },
+        )
+        .try_flatten()
+    }
+
+    /// Retrieve all locations in the given ISO country codes using a custom HTTP client and endpoint.
+    pub async fn get_all_custom(
+        key: &ApiKey,
+        client: &Client,
+        endpoint: Option<&str>,
+        countries: &[&str],
+    ) -> Result<Self, GetError> {
+        Self::get_filtered(key, client, endpoint, &LocationQuery::default(), countries).await
+    }
+
+    /// Retrieve US locations within a radius (in miles) of a point using a custom HTTP client and endpoint.
+    pub async fn get_near(
+        key: &ApiKey,
+        client: &Client,
+        endpoint: Option<&str>,
+        latitude: f64,
+        longitude: f64,
+        radius_miles: f64,
+    ) -> Result<Self, GetError> {
+        let query = LocationQuery {
+            latitude,
+            longitude,
+            radius: (radius_miles * METERS_PER_MILE).round() as u64,
+            ..Default::default()
+        };
+        Self::get_with_query(key, client, endpoint, &query).await
+    }
+
+    /// Retrieve all US locations matching a custom query using a custom HTTP client and endpoint.
+    pub async fn get_with_query(
+        key: &ApiKey,
+        client: &Client,
+        endpoint: Option<&str>,
+        query: &LocationQuery,
+    ) -> Result<Self, GetError> {
+        Self::get_filtered(key, client, endpoint, query, US_ONLY).await
+    }
+
+    async fn get_filtered(
+        key: &ApiKey,
+        client: &Client,
+        endpoint: Option<&str>,
+        query: &LocationQuery,
+        countries: &[&str],
+    ) -> Result<Self, GetError> {
+        let pages = Self::get_pages(
+            key,
+            client,
+            endpoint,
+            query,
+            DEFAULT_MAX_ATTEMPTS,
+            DEFAULT_RETRY_BASE_DELAY,
+            &Metrics::default(),
+        )
+        .await?;
+        Ok(pages.finish(countries))
+    }
+
+    /// Fetch pages until one comes back short, retrying each page on its own.
+    async fn get_pages(
+        key: &ApiKey,
+        client: &Client,
+        endpoint: Option<&str>,
+        query: &LocationQuery,
+        max_attempts: u32,
+        base_delay: Duration,
+        metrics: &Metrics,
+    ) -> Result<Pages, GetError> {
+        let mut pages = Pages::new(query);
+        loop {
+            let mut attempt = 0;
+            let page = with_retries(max_attempts, base_delay, || {
+                attempt += 1;
+                if attempt > 1 {
+                    metrics.record_retry();
+                }
+                Self::get_page(key, client, endpoint, query, pages.next_index, metrics)
+            })
             .await?;
-        if !response.status().is_success() {
-            return Err(GetError::ResponseError(response.status()));
+            if !pages.push(page) {
+                break;
+            }
+        }
+        Ok(pages)
+    }
+
+    pub(crate) async fn get_page(
+        key: &ApiKey,
+        client: &Client,
+        endpoint: Option<&str>,
+        query: &LocationQuery,
+        page_index: u32,
+        metrics: &Metrics,
+    ) -> Result<locations::Response, GetError> {
+        let url = endpoint.unwrap_or(DEFAULT_LOCATION_INFO_ENDPOINT);
+        let span = tracing::debug_span!("locations_request", url, page_index);
+        async {
+            let request = client
+                .post(url)
+                .header("Content-Type", "application/json")
+                .body(query.to_request_body(page_index).to_string());
+            let response = key
+                .authorize(request)
+                .send()
+                .await
+                .inspect_err(|_| metrics.record_request_error())?;
+            tracing::debug!(status = %response.status(), "locations response");
+            metrics.record_response(response.status());
+            if !response.status().is_success() {
+                return Err(GetError::ResponseError {
+                    status: response.status(),
+                    body: error_body(response).await,
+                });
+            }
+            let response_body = read_body(response, DEFAULT_MAX_BODY_BYTES).await?;
+            metrics.record_bytes(response_body.len());
+            Ok(serde_json::from_str(response_body.as_str())?)
+        }
+        .instrument(span)
+        .await
+    }
+
+    /// Retrieve all US locations with a blocking HTTP client, without needing an async runtime.
+    /// Behaves like [`Locations::get_all_us_custom`].
+    #[cfg(feature = "blocking")]
+    pub fn get_all_us_custom_blocking(
+        key: &ApiKey,
+        client: &reqwest::blocking::Client,
+        endpoint: Option<&str>,
+    ) -> Result<Self, GetError> {
+        let url = endpoint.unwrap_or(DEFAULT_LOCATION_INFO_ENDPOINT);
+        let query = LocationQuery::default();
+        let mut pages = Pages::new(&query);
+        loop {
+            let page_index = pages.next_index;
+            let _span = tracing::debug_span!("locations_request", url, page_index).entered();
+            let page =
+                with_retries_blocking(DEFAULT_MAX_ATTEMPTS, DEFAULT_RETRY_BASE_DELAY, || {
+                    let request = client
+                        .post(url)
+                        .header("Content-Type", "application/json")
+                        .body(query.to_request_body(page_index).to_string());
+                    let response = key.authorize_blocking(request).send()?;
+                    tracing::debug!(status = %response.status(), "locations response");
+                    if !response.status().is_success() {
+                        return Err(GetError::ResponseError {
+                            status: response.status(),
+                            body: error_body_blocking(response),
+                        });
+                    }
+                    let response_body = read_body_blocking(response, DEFAULT_MAX_BODY_BYTES)?;
+                    Ok(serde_json::from_str(response_body.as_str())?)
+                })?;
+            if !pages.push(page) {
+                break;
+            }
+        }
+        Ok(pages.finish(US_ONLY).dedup_sorted())
+    }
+
+    /// Retrieve a single location by restaurant number, in any country. Returns `None` if there's no such restaurant.
+    pub async fn get_one(
+        key: &ApiKey,
+        client: &Client,
+        id: LocationId,
+        endpoint: Option<&str>,
+    ) -> Result<Option<Location>, GetError> {
+        let base_url = endpoint.unwrap_or(DEFAULT_LOCATION_INFO_ENDPOINT);
+        let url = format!("{}/{id}", base_url.trim_end_matches('/'));
+        let span = tracing::debug_span!("location_request", %url, store_id = %id);
+        let response = with_retries(DEFAULT_MAX_ATTEMPTS, DEFAULT_RETRY_BASE_DELAY, || async {
+            let response = key.authorize(client.get(&url)).send().await?;
+            tracing::debug!(status = %response.status(), "location response");
+            match response.status() {
+                StatusCode::NOT_FOUND => Ok(None),
+                status if !status.is_success() => Err(GetError::ResponseError {
+                    status,
+                    body: error_body(response).await,
+                }),
+                _ => {
+                    let body = read_body(response, DEFAULT_MAX_BODY_BYTES).await?;
+                    Ok(Some(serde_json::from_str::<locations::Location>(&body)?))
+                }
+            }
+        })
+        .instrument(span)
+        .await?;
+        let Some(location) = response else {
+            return Ok(None);
+        };
+        let address = location
+            .addresses
+            .first()
+            .ok_or_else(|| GetError::TranslateError(format!("location {id} has no address")))?;
+        to_location(&location, address)
+            .map(Some)
+            .map_err(|e| GetError::TranslateError(format!("location {id}: {e}")))
+    }
+
+    /// Number of locations.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether there are no locations, which for a full fetch usually means a misconfigured endpoint.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Sort by ID, keeping only the first of any locations with the same ID, so output is deterministic.
+    pub fn dedup_sorted(mut self) -> Self {
+        self.0.sort_by_key(|location| location.id);
+        self.0.dedup_by_key(|location| location.id);
+        self
+    }
+
+    /// Group locations by zip code, for repeated lookups.
+    pub fn index_by_zip(self) -> HashMap<ZipCode, Vec<Location>> {
+        let mut index: HashMap<ZipCode, Vec<Location>> = HashMap::new();
+        for location in self.0 {
+            index
+                .entry(location.zip_code.clone())
+                .or_default()
+                .push(location);
+        }
+        index
+    }
+
+    /// All locations in a zip code.
+    pub fn by_zip(&self, zip_code: &str) -> Vec<&Location> {
+        self.0
+            .iter()
+            .filter(|location| location.zip_code.as_str() == zip_code)
+            .collect()
+    }
+
+    /// Keep only locations in a two-letter state code, ignoring case.
+    /// Also returns how many locations were dropped because they have no state.
+    /// Matches stores like [`LocationFilter::state`].
+    pub fn in_state(self, state: &str) -> (Self, usize) {
+        let missing_state = self
+            .0
+            .iter()
+            .filter(|location| location.state.is_none())
+            .count();
+        (
+            LocationFilter::new().state(state).apply(self),
+            missing_state,
+        )
+    }
+
+    /// Render as CSV with a header row. Missing fields are left blank.
+    pub fn to_csv(&self) -> String {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        let rows = self.0.iter().map(|location| {
+            [
+                location.id.to_string(),
+                location.zip_code.to_string(),
+                location.name.clone().unwrap_or_default(),
+                location.street.clone().unwrap_or_default(),
+                location.city.clone().unwrap_or_default(),
+                location.state.clone().unwrap_or_default(),
+            ]
+        });
+        for row in std::iter::once(LOCATION_CSV_HEADER.map(str::to_string)).chain(rows) {
+            writer
+                .write_record(&row)
+                .expect("writing CSV to memory can't fail");
         }
-        let response_body = response.text().await.map_err(GetError::ResponseBodyError)?;
-        let parsed_body: locations::Response = serde_json::from_str(response_body.as_str())?;
-        Ok(Locations(get_us_locations(parsed_body)))
+        let bytes = writer
+            .into_inner()
+            .expect("writing CSV to memory can't fail");
+        String::from_utf8(bytes).expect("CSV fields are all valid UTF-8")
+    }
+
+    /// Render as a GeoJSON `FeatureCollection` of points. Locations without coordinates are skipped.
+    pub fn to_geojson(&self) -> Value {
+        let features: Vec<Value> = self
+            .0
+            .iter()
+            .filter_map(|location| {
+                Some(json!({
+                    "type": "Feature",
+                    "geometry": {
+                        "type": "Point",
+                        "coordinates": [location.lon?, location.lat?],
+                    },
+                    "properties": {
+                        "id": location.id,
+                        "zip_code": location.zip_code,
+                    },
+                }))
+            })
+            .collect();
+        json!({"type": "FeatureCollection", "features": features})
+    }
+
+    /// Locations missing a latitude or longitude, which [`Locations::to_geojson`] leaves out.
+    pub fn without_coordinates(&self) -> impl Iterator<Item = &Location> {
+        self.0
+            .iter()
+            .filter(|location| location.lat.is_none() || location.lon.is_none())
     }
 
     pub async fn load<P: AsRef<Path>>(path: P) -> Result<Self, LoadError> {
-        let file_contents = tokio::fs::read_to_string(path).await?;
-        Ok(Self(serde_json::from_str(file_contents.as_str())?))
+        let path = path.as_ref();
+        let file_contents = read_file(path).await?;
+        Ok(Self(parse_file(path, &file_contents)?))
+    }
+
+    /// Load a file, skipping array entries that aren't valid locations instead of failing.
+    /// The file must still be a JSON array. Skipped entries are logged as a warning.
+    pub async fn load_lenient<P: AsRef<Path>>(path: P) -> Result<Self, LoadError> {
+        let path = path.as_ref();
+        let file_contents = read_file(path).await?;
+        let entries: Vec<Value> = parse_file(path, &file_contents)?;
+        let total = entries.len();
+        let locations: Vec<Location> = entries
+            .into_iter()
+            .filter_map(|entry| serde_json::from_value(entry).ok())
+            .collect();
+        let dropped = total - locations.len();
+        if dropped > 0 {
+            tracing::warn!(dropped, total, "skipped malformed location entries");
+        }
+        Ok(Self(locations))
     }
 
     pub async fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), SaveError> {
-        let serialized = serde_json::to_string(&self.0)?;
-        tokio::fs::write(path, serialized).await?;
-        Ok(())
+        self.save_with_retries(path, 1).await
+    }
+
+    /// Save, retrying the write up to `max_attempts` times if it fails with a transient IO error.
+    pub async fn save_with_retries<P: AsRef<Path>>(
+        &self,
+        path: P,
+        max_attempts: u32,
+    ) -> Result<(), SaveError> {
+        let path = path.as_ref();
+        let serialized =
+            serde_json::to_string(&self.0).map_err(|source| SaveError::SerializeError {
+                path: path.to_path_buf(),
+                source,
+            })?;
+        retry_transient_io(max_attempts, || tokio::fs::write(path, &serialized))
+            .await
+            .map_err(|source| SaveError::WriteError {
+                path: path.to_path_buf(),
+                source,
+            })
     }
 }
 
-fn get_zip_code(location_id: &i32, address: &locations::Address) -> String {
-    ZIP_CODE_OVERRIDES
+async fn read_file(path: &Path) -> Result<String, LoadError> {
+    tokio::fs::read_to_string(path)
+        .await
+        .map_err(|source| LoadError::ReadError {
+            path: path.to_path_buf(),
+            source,
+        })
+}
+
+fn parse_file<T: DeserializeOwned>(path: &Path, contents: &str) -> Result<T, LoadError> {
+    serde_json::from_str(contents).map_err(|source| LoadError::ParseError {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Composable in-memory filters for [`Locations`]. A location is kept only if it passes every filter that was set.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LocationFilter {
+    zips: Option<HashSet<ZipCode>>,
+    state: Option<String>,
+    within: Option<(f64, f64, f64)>,
+}
+
+impl LocationFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep locations in this zip code. Combined with other zip filters, any of the zip codes matches.
+    /// A ZIP+4 matches its 5-digit zip code; see [`LocationFilter::zips`] for the errors.
+    pub fn zip(self, zip_code: &str) -> Result<Self, ZipCodeError> {
+        self.zips(&[zip_code])
+    }
+
+    /// Keep locations in any of these zip codes, each parsed as a [`ZipCode`].
+    /// Fails on the first that isn't a valid US zip code, rather than silently matching nothing.
+    pub fn zips(mut self, zip_codes: &[&str]) -> Result<Self, ZipCodeError> {
+        let zip_codes = zip_codes
+            .iter()
+            .map(|zip_code| zip_code.parse())
+            .collect::<Result<Vec<ZipCode>, _>>()?;
+        self.zips.get_or_insert_with(HashSet::new).extend(zip_codes);
+        Ok(self)
+    }
+
+    /// Keep locations in a two-letter state code, ignoring case. Locations without a state are dropped.
+    pub fn state(mut self, state: &str) -> Self {
+        self.state = Some(state.to_string());
+        self
+    }
+
+    /// Keep locations within `miles` of a point. Locations without coordinates are dropped.
+    pub fn within(mut self, latitude: f64, longitude: f64, miles: f64) -> Self {
+        self.within = Some((latitude, longitude, miles));
+        self
+    }
+
+    /// Whether a location passes every filter.
+    pub fn matches(&self, location: &Location) -> bool {
+        let zip_matches = self
+            .zips
+            .as_ref()
+            .is_none_or(|zips| zips.contains(&location.zip_code));
+        let state_matches = self.state.as_ref().is_none_or(|state| {
+            location
+                .state
+                .as_ref()
+                .is_some_and(|location_state| location_state.eq_ignore_ascii_case(state))
+        });
+        let within_matches = self.within.is_none_or(|(latitude, longitude, miles)| {
+            match (location.lat, location.lon) {
+                (Some(lat), Some(lon)) => {
+                    haversine_miles((latitude, longitude), (lat, lon)) <= miles
+                }
+                _ => false,
+            }
+        });
+        zip_matches && state_matches && within_matches
+    }
+
+    pub fn apply(self, locations: Locations) -> Locations {
+        Locations(
+            locations
+                .0
+                .into_iter()
+                .filter(|location| self.matches(location))
+                .collect(),
+        )
+    }
+}
+
+/// Great-circle distance in miles between two `(latitude, longitude)` points, in degrees.
+fn haversine_miles(from: (f64, f64), to: (f64, f64)) -> f64 {
+    let (from_lat, to_lat) = (from.0.to_radians(), to.0.to_radians());
+    let delta_lat = to_lat - from_lat;
+    let delta_lon = (to.1 - from.1).to_radians();
+    let a = (delta_lat / 2.0).sin().powi(2)
+        + from_lat.cos() * to_lat.cos() * (delta_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_MILES * a.sqrt().asin()
+}
+
+/// Pages of locations collected so far, keeping locations repeated within or across pages only once.
+struct Pages {
+    page_size: u32,
+    next_index: u32,
+    data: Vec<locations::Location>,
+    /// Where each ID seen so far is in `data`, or `None` once it has been taken.
+    seen: HashMap<i32, Option<usize>>,
+}
+
+impl Pages {
+    fn new(query: &LocationQuery) -> Self {
+        Self {
+            page_size: query.page_size,
+            next_index: 0,
+            data: Vec::new(),
+            seen: HashMap::new(),
+        }
+    }
+
+    /// Add the next page, returning whether there may be more after it.
+    /// A short page is the last one, as is a page with no new IDs, since a service ignoring `pageIndex`
+    /// would otherwise be asked for the same page forever. At most [`MAX_LOCATION_PAGES`] are fetched.
+    /// Of several entries with the same ID, the first with a postal code wins, or else the first.
+    fn push(&mut self, page: locations::Response) -> bool {
+        let page_len = page.data.len();
+        let mut new_ids = 0;
+        for location in page.data {
+            match self.seen.get(&location.id) {
+                None => {
+                    self.seen.insert(location.id, Some(self.data.len()));
+                    self.data.push(location);
+                    new_ids += 1;
+                }
+                Some(Some(index)) => {
+                    let kept = &mut self.data[*index];
+                    if has_postal_code(&location) && !has_postal_code(kept) {
+                        tracing::debug!(
+                            location_id = location.id,
+                            "replacing duplicate location without a postal code"
+                        );
+                        *kept = location;
+                    }
+                }
+                Some(None) => {}
+            }
+        }
+        self.next_index += 1;
+        if page_len == 0 || page_len < self.page_size as usize {
+            return false;
+        }
+        if new_ids == 0 {
+            tracing::warn!(
+                page_index = self.next_index - 1,
+                "full page of locations had no new IDs, stopping"
+            );
+            return false;
+        }
+        if self.next_index >= MAX_LOCATION_PAGES {
+            tracing::warn!(
+                pages = self.next_index,
+                "stopping after the maximum number of location pages"
+            );
+            return false;
+        }
+        true
+    }
+
+    /// Convert the locations added since the last call, leaving the ones already seen for deduplication.
+    /// A taken location without a postal code can still be replaced by a later duplicate that has one.
+    fn take(&mut self, countries: &[&str]) -> Vec<Location> {
+        let data = std::mem::take(&mut self.data);
+        for location in &data {
+            if has_postal_code(location) {
+                self.seen.insert(location.id, None);
+            } else {
+                self.seen.remove(&location.id);
+            }
+        }
+        get_locations(locations::Response { data }, countries)
+    }
+
+    fn finish(mut self, countries: &[&str]) -> Locations {
+        Locations(self.take(countries))
+    }
+}
+
+fn has_postal_code(location: &locations::Location) -> bool {
+    location
+        .addresses
+        .first()
+        .is_some_and(|address| address.postal_code.is_some())
+}
+
+fn get_zip_code(location_id: &i32, address: &locations::Address) -> Result<ZipCode, ZipCodeError> {
+    let zip_code = ZIP_CODE_OVERRIDES
         .get(location_id)
         .copied()
         .or(address.postal_code.as_deref())
-        .map(|x| {
-            if x.len() > 5 {
-                x[0..5].to_string()
-            } else {
-                x.to_string()
-            }
-        })
-        .unwrap()
+        .ok_or(ZipCodeError::Missing)?;
+    // Only US zip codes are validated; e.g. Canadian postal codes are kept whole.
+    match address.country_code.as_str() {
+        "US" => zip_code.parse(),
+        _ => Ok(ZipCode::from_postal_code(zip_code)),
+    }
+}
+
+fn to_location(
+    location: &locations::Location,
+    address: &locations::Address,
+) -> Result<Location, ZipCodeError> {
+    Ok(Location {
+        id: LocationId(location.id),
+        zip_code: get_zip_code(&location.id, address)?,
+        name: location.name.clone(),
+        street: address.street.clone(),
+        city: address.city.clone(),
+        state: address.state.clone(),
+        lat: address.latitude,
+        lon: address.longitude,
+    })
 }
 
-fn get_us_locations(data: locations::Response) -> Vec<Location> {
+fn get_locations(data: locations::Response, countries: &[&str]) -> Vec<Location> {
     data.data
         .iter()
         .filter_map(|location| match location.addresses.first() {
-            Some(address) if address.country_code == "US" => Some(Location {
-                id: location.id,
-                zip_code: get_zip_code(&location.id, address),
-            }),
-            _ => None,
+            Some(address) if countries.contains(&address.country_code.as_str()) => {
+                to_location(location, address)
+                    .inspect_err(|e| {
+                        tracing::warn!(location_id = location.id, error = %e, "skipping location");
+                    })
+                    .ok()
+            }
+            Some(address) => {
+                tracing::debug!(
+                    location_id = location.id,
+                    country_code = %address.country_code,
+                    "skipping location outside the requested countries"
+                );
+                None
+            }
+            None => {
+                tracing::warn!(
+                    location_id = location.id,
+                    "skipping location without an address"
+                );
+                None
+            }
         })
         .collect()
 }
 
+impl From<Vec<Location>> for Locations {
+    fn from(locations: Vec<Location>) -> Self {
+        Self(locations)
+    }
+}
+
+impl From<Locations> for Vec<Location> {
+    fn from(locations: Locations) -> Self {
+        locations.0
+    }
+}
+
 impl IntoIterator for Locations {
     type Item = Location;
     type IntoIter = std::vec::IntoIter<Location>;
@@ -132,12 +884,22 @@ impl IntoIterator for Locations {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::constants::API_KEY_HEADER;
     use httpmock::prelude::*;
+    use proptest::prelude::*;
     use std::io::Write;
     use tempfile::NamedTempFile;
 
     const FAKE_API_KEY: &str = "fake-api-key";
 
+    fn location(id: i32, zip_code: &str) -> Location {
+        Location {
+            id: LocationId(id),
+            zip_code: zip_code.parse().unwrap(),
+            ..Default::default()
+        }
+    }
+
     #[tokio::test]
     async fn get_success() {
         // Arrange
@@ -146,10 +908,16 @@ mod tests {
             "data": [
                 {
                     "restaurantNumber": 1234,
+                    "restaurantName": "Main St",
                     "addresses": [
                         {
+                            "addressLine1": "1 Main St",
+                            "locality": "Springfield",
+                            "administrativeArea": "IL",
                             "postalCode": "12345",
-                            "countryCode": "US"
+                            "countryCode": "US",
+                            "latitude": 39.78,
+                            "longitude": -89.65
                         }
                     ]
                 }
@@ -160,7 +928,7 @@ mod tests {
                 let body_matcher = Regex::new(".+").unwrap();
                 when.path("/")
                     .header(API_KEY_HEADER, FAKE_API_KEY)
-                    .json_body(DEFAULT_REQUEST_BODY.clone())
+                    .json_body(LocationQuery::default().to_request_body(0))
                     .method(POST)
                     .body_matches(body_matcher);
                 then.status(200).json_body(response_json);
@@ -181,78 +949,449 @@ mod tests {
         );
         let locations = locations.unwrap();
         assert_eq!(locations.0.len(), 1);
-        assert_eq!(locations.0[0].id, 1234);
+        assert_eq!(locations.0[0].id, LocationId(1234));
         assert_eq!(locations.0[0].zip_code, "12345");
+        assert_eq!(locations.0[0].name.as_deref(), Some("Main St"));
+        assert_eq!(locations.0[0].street.as_deref(), Some("1 Main St"));
+        assert_eq!(locations.0[0].city.as_deref(), Some("Springfield"));
+        assert_eq!(locations.0[0].state.as_deref(), Some("IL"));
+        assert_eq!(locations.0[0].lat, Some(39.78));
+        assert_eq!(locations.0[0].lon, Some(-89.65));
         locations_mock.assert();
     }
 
     #[tokio::test]
-    async fn get_invalid_url() {
+    async fn get_near_success() {
         // Arrange
+        let server = MockServer::start_async().await;
+        let response_json = json!({
+            "data": [
+                {
+                    "restaurantNumber": 1234,
+                    "addresses": [{ "postalCode": "12345", "countryCode": "US" }]
+                },
+                {
+                    "restaurantNumber": 5678,
+                    "addresses": [{ "postalCode": "M5V 2T6", "countryCode": "CA" }]
+                }
+            ]
+        });
+        let locations_mock = server
+            .mock_async(|when, then| {
+                when.path("/").method(POST).json_body_partial(
+                    r#"{"latitude": 40.7128, "longitude": -74.006, "radius": 16093, "conceptIds": ["CMG"]}"#,
+                );
+                then.status(200).json_body(response_json);
+            })
+            .await;
+        let url = server.url("/");
         let client = reqwest::Client::new();
         let api_key = ApiKey::from_raw(FAKE_API_KEY);
 
         // Act
-        let locations =
-            Locations::get_all_us_custom(&api_key, &client, Some("http://test.invalid")).await;
+        let locations = Locations::get_near(
+            &api_key,
+            &client,
+            Some(url.as_str()),
+            40.7128,
+            -74.006,
+            10.0,
+        )
+        .await;
 
         // Assert
-        assert!(locations.is_err());
-        assert!(matches!(locations.unwrap_err(), GetError::RequestError(_)));
+        assert!(
+            locations.is_ok(),
+            "Failed to get locations: {:?}",
+            locations.unwrap_err()
+        );
+        let locations = locations.unwrap();
+        assert_eq!(locations.0.len(), 1);
+        assert_eq!(locations.0[0].id, LocationId(1234));
+        locations_mock.assert();
     }
 
     #[tokio::test]
-    async fn get_bad_status() {
+    async fn get_unauthenticated_omits_header() {
         // Arrange
         let server = MockServer::start_async().await;
         let locations_mock = server
             .mock_async(|when, then| {
-                when.path("/");
-                then.status(403);
+                when.path("/").matches(|request| {
+                    !request
+                        .headers
+                        .iter()
+                        .flatten()
+                        .any(|(name, _)| name.eq_ignore_ascii_case(API_KEY_HEADER))
+                });
+                then.status(200).json_body(json!({ "data": [] }));
             })
             .await;
         let url = server.url("/");
         let client = reqwest::Client::new();
-        let api_key = ApiKey::from_raw(FAKE_API_KEY);
+        let api_key = ApiKey::unauthenticated();
 
         // Act
         let locations = Locations::get_all_us_custom(&api_key, &client, Some(url.as_str())).await;
 
         // Assert
-        assert!(locations.is_err());
-        assert!(matches!(locations.unwrap_err(), GetError::ResponseError(_)));
+        assert!(
+            locations.is_ok(),
+            "Failed to get locations: {:?}",
+            locations.unwrap_err()
+        );
         locations_mock.assert();
     }
 
     #[tokio::test]
-    async fn get_bad_json() {
+    async fn get_with_custom_concepts() {
         // Arrange
         let server = MockServer::start_async().await;
         let locations_mock = server
             .mock_async(|when, then| {
-                when.path("/");
-                then.status(200)
-                    .header("Content-Type", "application/json")
-                    .body(r#"{"error": "something is amiss" }"#);
+                when.path("/")
+                    .method(POST)
+                    .json_body_partial(r#"{"conceptIds": ["CMG", "FRM"]}"#);
+                then.status(200).json_body(json!({ "data": [] }));
             })
             .await;
         let url = server.url("/");
         let client = reqwest::Client::new();
         let api_key = ApiKey::from_raw(FAKE_API_KEY);
+        let query = LocationQueryBuilder::default()
+            .concept_ids(vec!["CMG".to_string(), "FRM".to_string()])
+            .build()
+            .unwrap();
 
         // Act
-        let locations = Locations::get_all_us_custom(&api_key, &client, Some(url.as_str())).await;
+        let locations =
+            Locations::get_with_query(&api_key, &client, Some(url.as_str()), &query).await;
 
         // Assert
-        assert!(locations.is_err());
-        assert!(matches!(locations.unwrap_err(), GetError::ParseError(_)));
+        assert!(
+            locations.is_ok(),
+            "Failed to get locations: {:?}",
+            locations.unwrap_err()
+        );
         locations_mock.assert();
     }
 
-    #[tokio::test]
-    async fn get_non_us_filtered() {
+    #[test]
+    fn default_query_body_is_unchanged() {
         // Arrange
-        let server = MockServer::start_async().await;
+        let query = LocationQuery::default();
+
+        // Act
+        let body = query.to_request_body(2);
+
+        // Assert
+        assert_eq!(
+            body,
+            json!({
+                "latitude": 0.0,
+                "longitude": 0.0,
+                "radius": 999999999,
+                "restaurantStatuses": ["OPEN", "LAB"],
+                "conceptIds": ["CMG"],
+                "orderBy": "distance",
+                "orderByDescending": false,
+                "pageSize": 4000,
+                "pageIndex": 2,
+                "embeds": {
+                    "addressTypes": ["MAIN"],
+                    "realHours": false,
+                    "directions": false,
+                    "catering": false,
+                    "onlineOrdering": true,
+                    "timezone": false,
+                    "marketing": false,
+                    "chipotlane": false,
+                    "sustainability": false,
+                    "experience": false,
+                },
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn get_with_closed_restaurants_and_hours() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        let locations_mock = server
+            .mock_async(|when, then| {
+                when.path("/").method(POST).json_body_partial(
+                    r#"{"restaurantStatuses": ["OPEN", "LAB", "CLOSED"], "embeds": {"realHours": true}}"#,
+                );
+                then.status(200).json_body(json!({ "data": [] }));
+            })
+            .await;
+        let url = server.url("/");
+        let client = reqwest::Client::new();
+        let api_key = ApiKey::from_raw(FAKE_API_KEY);
+        let query = LocationQueryBuilder::default()
+            .restaurant_statuses(["OPEN", "LAB", "CLOSED"].map(String::from))
+            .embeds(
+                LocationEmbedsBuilder::default()
+                    .real_hours(true)
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        // Act
+        let locations =
+            Locations::get_with_query(&api_key, &client, Some(url.as_str()), &query).await;
+
+        // Assert
+        assert!(
+            locations.is_ok(),
+            "Failed to get locations: {:?}",
+            locations.unwrap_err()
+        );
+        locations_mock.assert();
+    }
+
+    #[tokio::test]
+    async fn get_invalid_url() {
+        // Arrange
+        let client = reqwest::Client::new();
+        let api_key = ApiKey::from_raw(FAKE_API_KEY);
+
+        // Act
+        let locations =
+            Locations::get_all_us_custom(&api_key, &client, Some("http://test.invalid")).await;
+
+        // Assert
+        assert!(locations.is_err());
+        assert!(matches!(locations.unwrap_err(), GetError::RequestError(_)));
+    }
+
+    #[tokio::test]
+    async fn get_bad_status() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        let locations_mock = server
+            .mock_async(|when, then| {
+                when.path("/");
+                then.status(403);
+            })
+            .await;
+        let url = server.url("/");
+        let client = reqwest::Client::new();
+        let api_key = ApiKey::from_raw(FAKE_API_KEY);
+
+        // Act
+        let locations = Locations::get_all_us_custom(&api_key, &client, Some(url.as_str())).await;
+
+        // Assert
+        assert!(locations.is_err());
+        assert!(matches!(
+            locations.unwrap_err(),
+            GetError::ResponseError { .. }
+        ));
+        locations_mock.assert();
+    }
+
+    #[tokio::test]
+    async fn get_retries_server_errors() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        let locations_mock = server
+            .mock_async(|when, then| {
+                when.path("/");
+                then.status(503);
+            })
+            .await;
+        let url = server.url("/");
+        let client = reqwest::Client::new();
+        let api_key = ApiKey::from_raw(FAKE_API_KEY);
+
+        // Act
+        let locations = Locations::get_all_us_custom_with_retries(
+            &api_key,
+            &client,
+            Some(url.as_str()),
+            2,
+            Duration::ZERO,
+        )
+        .await;
+
+        // Assert
+        assert!(matches!(
+            locations.unwrap_err(),
+            GetError::ResponseError {
+                status: reqwest::StatusCode::SERVICE_UNAVAILABLE,
+                ..
+            }
+        ));
+        locations_mock.assert_hits(2);
+    }
+
+    #[tokio::test]
+    async fn get_paginated() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        let page = |ids: &[i32]| {
+            json!({
+                "data": ids
+                    .iter()
+                    .map(|id| json!({
+                        "restaurantNumber": id,
+                        "addresses": [{"postalCode": "12345", "countryCode": "US"}]
+                    }))
+                    .collect::<Vec<_>>()
+            })
+        };
+        let first_page_mock = server
+            .mock_async(|when, then| {
+                when.path("/").json_body_partial(r#"{"pageIndex": 0}"#);
+                then.status(200).json_body(page(&[1, 2, 3]));
+            })
+            .await;
+        let second_page_mock = server
+            .mock_async(|when, then| {
+                when.path("/").json_body_partial(r#"{"pageIndex": 1}"#);
+                then.status(200).json_body(page(&[3, 4]));
+            })
+            .await;
+        let url = server.url("/");
+        let client = reqwest::Client::new();
+        let api_key = ApiKey::from_raw(FAKE_API_KEY);
+        let query = LocationQueryBuilder::default()
+            .page_size(3)
+            .build()
+            .unwrap();
+
+        // Act
+        let locations =
+            Locations::get_with_query(&api_key, &client, Some(url.as_str()), &query).await;
+
+        // Assert
+        let ids: Vec<i32> = locations.unwrap().0.iter().map(|l| l.id.0).collect();
+        assert_eq!(ids, vec![1, 2, 3, 4]);
+        first_page_mock.assert();
+        second_page_mock.assert();
+    }
+
+    #[tokio::test]
+    async fn get_paginated_stops_when_page_index_is_ignored() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        let locations_mock = server
+            .mock_async(|when, then| {
+                when.path("/");
+                then.status(200).json_body(json!({
+                    "data": [
+                        {"restaurantNumber": 1, "addresses": [{"postalCode": "12345", "countryCode": "US"}]},
+                        {"restaurantNumber": 2, "addresses": [{"postalCode": "54321", "countryCode": "US"}]}
+                    ]
+                }));
+            })
+            .await;
+        let url = server.url("/");
+        let client = reqwest::Client::new();
+        let api_key = ApiKey::from_raw(FAKE_API_KEY);
+        let query = LocationQueryBuilder::default()
+            .page_size(2)
+            .build()
+            .unwrap();
+
+        // Act
+        let locations =
+            Locations::get_with_query(&api_key, &client, Some(url.as_str()), &query).await;
+
+        // Assert
+        let ids: Vec<i32> = locations.unwrap().0.iter().map(|l| l.id.0).collect();
+        assert_eq!(ids, vec![1, 2]);
+        locations_mock.assert_hits(2);
+    }
+
+    #[test]
+    fn pages_stop_at_page_limit() {
+        // Arrange
+        let query = LocationQuery {
+            page_size: 1,
+            ..Default::default()
+        };
+        let mut pages = Pages::new(&query);
+        let page = |id: u32| {
+            serde_json::from_value::<locations::Response>(json!({
+                "data": [{"restaurantNumber": id, "addresses": [{"postalCode": "12345", "countryCode": "US"}]}]
+            }))
+            .unwrap()
+        };
+
+        // Act
+        let mut fetched = 0;
+        while pages.push(page(fetched)) {
+            fetched += 1;
+        }
+
+        // Assert
+        assert_eq!(pages.next_index, MAX_LOCATION_PAGES);
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn get_blocking_success() {
+        // Arrange
+        let server = MockServer::start();
+        let locations_mock = server.mock(|when, then| {
+            when.path("/")
+                .header(API_KEY_HEADER, FAKE_API_KEY)
+                .json_body(LocationQuery::default().to_request_body(0))
+                .method(POST);
+            then.status(200).json_body(json!({
+                "data": [
+                    {"restaurantNumber": 3, "addresses": [{"postalCode": "12345", "countryCode": "US"}]},
+                    {"restaurantNumber": 2, "addresses": [{"postalCode": "M5V 2T6", "countryCode": "CA"}]},
+                    {"restaurantNumber": 1, "addresses": [{"postalCode": "54321", "countryCode": "US"}]}
+                ]
+            }));
+        });
+        let url = server.url("/");
+        let client = reqwest::blocking::Client::new();
+        let api_key = ApiKey::from_raw(FAKE_API_KEY);
+
+        // Act
+        let locations = Locations::get_all_us_custom_blocking(&api_key, &client, Some(&url));
+
+        // Assert
+        let ids: Vec<i32> = locations.unwrap().0.iter().map(|l| l.id.0).collect();
+        assert_eq!(ids, vec![1, 3]);
+        locations_mock.assert();
+    }
+
+    #[tokio::test]
+    async fn get_bad_json() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        let locations_mock = server
+            .mock_async(|when, then| {
+                when.path("/");
+                then.status(200)
+                    .header("Content-Type", "application/json")
+                    .body(r#"{"error": "something is amiss" }"#);
+            })
+            .await;
+        let url = server.url("/");
+        let client = reqwest::Client::new();
+        let api_key = ApiKey::from_raw(FAKE_API_KEY);
+
+        // Act
+        let locations = Locations::get_all_us_custom(&api_key, &client, Some(url.as_str())).await;
+
+        // Assert
+        assert!(locations.is_err());
+        assert!(matches!(locations.unwrap_err(), GetError::ParseError(_)));
+        locations_mock.assert();
+    }
+
+    #[tokio::test]
+    async fn get_non_us_filtered() {
+        // Arrange
+        let server = MockServer::start_async().await;
         let response_json = json!({
             "data": [
                 {
@@ -285,12 +1424,177 @@ mod tests {
         locations_mock.assert();
     }
 
+    #[tokio::test]
+    async fn get_duplicate_ids_prefer_postal_code() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        let location = |id: i32, postal_code: Option<&str>, street: &str| {
+            json!({
+                "restaurantNumber": id,
+                "addresses": [{"postalCode": postal_code, "countryCode": "US", "addressLine1": street}]
+            })
+        };
+        let locations_mock = server
+            .mock_async(|when, then| {
+                when.path("/");
+                then.status(200).json_body(json!({
+                    "data": [
+                        location(1234, None, "1 Null St"),
+                        location(5678, Some("54321"), "First Ave"),
+                        location(1234, Some("12345"), "2 Main St"),
+                        location(5678, Some("99999"), "Second Ave"),
+                    ]
+                }));
+            })
+            .await;
+        let url = server.url("/");
+        let client = reqwest::Client::new();
+        let api_key = ApiKey::from_raw(FAKE_API_KEY);
+
+        // Act
+        let locations = Locations::get_all_us_custom(&api_key, &client, Some(url.as_str())).await;
+
+        // Assert
+        let locations = locations.unwrap();
+        assert_eq!(locations.len(), 2);
+        assert_eq!(locations.0[0].id, LocationId(1234));
+        assert_eq!(locations.0[0].zip_code.as_str(), "12345");
+        assert_eq!(locations.0[0].street.as_deref(), Some("2 Main St"));
+        assert_eq!(locations.0[1].zip_code.as_str(), "54321");
+        locations_mock.assert();
+    }
+
+    #[test]
+    fn pages_replace_dropped_location_from_earlier_page() {
+        // Arrange
+        let query = LocationQuery {
+            page_size: 1,
+            ..Default::default()
+        };
+        let mut pages = Pages::new(&query);
+        let page = |postal_code: Option<&str>| {
+            serde_json::from_value::<locations::Response>(json!({
+                "data": [{"restaurantNumber": 1234, "addresses": [{"postalCode": postal_code, "countryCode": "US"}]}]
+            }))
+            .unwrap()
+        };
+
+        // Act
+        pages.push(page(None));
+        let first = pages.take(US_ONLY);
+        pages.push(page(Some("12345")));
+        let second = pages.take(US_ONLY);
+        pages.push(page(Some("99999")));
+        let third = pages.take(US_ONLY);
+
+        // Assert
+        assert!(first.is_empty());
+        assert_eq!(second.len(), 1);
+        assert_eq!(second[0].zip_code.as_str(), "12345");
+        assert!(third.is_empty());
+    }
+
+    #[tokio::test]
+    async fn get_null_postal_code_filtered() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        let response_json = json!({
+            "data": [
+                {
+                    "restaurantNumber": 1234,
+                    "addresses": [
+                        {
+                            "postalCode": null,
+                            "countryCode": "US"
+                        }
+                    ]
+                },
+                {
+                    "restaurantNumber": 5678,
+                    "addresses": [
+                        {
+                            "postalCode": "12345",
+                            "countryCode": "US"
+                        }
+                    ]
+                }
+            ]
+        });
+        let locations_mock = server
+            .mock_async(|when, then| {
+                when.path("/");
+                then.status(200).json_body(response_json);
+            })
+            .await;
+        let url = server.url("/");
+        let client = reqwest::Client::new();
+        let api_key = ApiKey::from_raw(FAKE_API_KEY);
+
+        // Act
+        let locations = Locations::get_all_us_custom(&api_key, &client, Some(url.as_str())).await;
+
+        // Assert
+        let locations = locations.unwrap();
+        assert_eq!(locations.0.len(), 1);
+        assert_eq!(locations.0[0].id, LocationId(5678));
+        locations_mock.assert();
+    }
+
+    #[tokio::test]
+    async fn get_canadian_locations() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        let response_json = json!({
+            "data": [
+                {
+                    "restaurantNumber": 1234,
+                    "addresses": [
+                        {
+                            "postalCode": "M5V 2T6",
+                            "countryCode": "CA"
+                        }
+                    ]
+                },
+                {
+                    "restaurantNumber": 5678,
+                    "addresses": [
+                        {
+                            "postalCode": "12345-6789",
+                            "countryCode": "US"
+                        }
+                    ]
+                }
+            ]
+        });
+        let locations_mock = server
+            .mock_async(|when, then| {
+                when.path("/");
+                then.status(200).json_body(response_json);
+            })
+            .await;
+        let url = server.url("/");
+        let client = reqwest::Client::new();
+        let api_key = ApiKey::from_raw(FAKE_API_KEY);
+
+        // Act
+        let locations =
+            Locations::get_all_custom(&api_key, &client, Some(url.as_str()), &["CA"]).await;
+
+        // Assert
+        let locations = locations.unwrap();
+        assert_eq!(locations.0.len(), 1);
+        assert_eq!(locations.0[0].id, LocationId(1234));
+        assert_eq!(locations.0[0].zip_code, "M5V 2T6");
+        locations_mock.assert();
+    }
+
     #[tokio::test]
     async fn load_success() {
         // Arrange
         let fake_location = Location {
-            id: 12345,
-            zip_code: "54321".to_string(),
+            id: LocationId(12345),
+            zip_code: "54321".parse().unwrap(),
+            ..Default::default()
         };
         let file_json = json!([fake_location]).to_string();
         let mut temp_file = NamedTempFile::new().unwrap();
@@ -310,6 +1614,27 @@ mod tests {
         assert_eq!(locations.0[0], fake_location);
     }
 
+    #[tokio::test]
+    async fn load_without_address_details() {
+        // Arrange
+        let file_json = json!([{"id": 12345, "zip_code": "54321"}]).to_string();
+        let mut temp_file = NamedTempFile::new().unwrap();
+        write!(temp_file, "{}", file_json).unwrap();
+
+        // Act
+        let locations = Locations::load(temp_file.path()).await;
+
+        // Assert
+        assert!(
+            locations.is_ok(),
+            "Failed to load locations: {:?}",
+            locations.unwrap_err()
+        );
+        let locations = locations.unwrap();
+        assert_eq!(locations.0[0].zip_code, "54321");
+        assert_eq!(locations.0[0].state, None);
+    }
+
     #[tokio::test]
     async fn load_invalid_file() {
         // Act
@@ -317,7 +1642,9 @@ mod tests {
 
         // Assert
         assert!(locations.is_err());
-        assert!(matches!(locations.unwrap_err(), LoadError::ReadError(_)));
+        let error = locations.unwrap_err();
+        assert!(error.to_string().contains("totally_nonexistent.json"));
+        assert!(matches!(error, LoadError::ReadError { .. }));
     }
 
     #[tokio::test]
@@ -331,16 +1658,60 @@ mod tests {
         let locations = Locations::load(temp_file.path()).await;
 
         // Assert
-        assert!(locations.is_err());
-        assert!(matches!(locations.unwrap_err(), LoadError::ParseError(_)));
+        let error = locations.unwrap_err();
+        assert!(error
+            .to_string()
+            .contains(&temp_file.path().display().to_string()));
+        assert!(matches!(error, LoadError::ParseError { .. }));
+    }
+
+    #[tokio::test]
+    async fn load_lenient_skips_malformed_entries() {
+        // Arrange
+        let mut temp_file = NamedTempFile::new().unwrap();
+        let json = json!([
+            {"id": 1234, "zip_code": "12345"},
+            {"id": "not a number", "zip_code": "12345"},
+            {"zip_code": "54321"},
+            "not a location",
+            {"id": 5678, "zip_code": "54321", "name": "Main St"}
+        ])
+        .to_string();
+        write!(temp_file, "{}", json).unwrap();
+
+        // Act
+        let strict = Locations::load(temp_file.path()).await;
+        let lenient = Locations::load_lenient(temp_file.path()).await;
+
+        // Assert
+        assert!(matches!(strict.unwrap_err(), LoadError::ParseError { .. }));
+        let ids: Vec<i32> = lenient.unwrap().0.iter().map(|l| l.id.0).collect();
+        assert_eq!(ids, vec![1234, 5678]);
+    }
+
+    #[tokio::test]
+    async fn load_lenient_rejects_non_array() {
+        // Arrange
+        let mut temp_file = NamedTempFile::new().unwrap();
+        write!(temp_file, "{}", json!({"not": "a location"})).unwrap();
+
+        // Act
+        let locations = Locations::load_lenient(temp_file.path()).await;
+
+        // Assert
+        assert!(matches!(
+            locations.unwrap_err(),
+            LoadError::ParseError { .. }
+        ));
     }
 
     #[tokio::test]
     async fn save_and_load_successful() {
         // Arrange
         let fake_location = Location {
-            id: 12345,
-            zip_code: "54321".to_string(),
+            id: LocationId(12345),
+            zip_code: "54321".parse().unwrap(),
+            ..Default::default()
         };
         let locations = Locations(vec![fake_location]);
         let temp_file = NamedTempFile::new().unwrap();
@@ -358,4 +1729,481 @@ mod tests {
         assert_eq!(locations.0.len(), 1);
         assert_eq!(&loaded_locations.0[0], &locations.0[0]);
     }
+
+    fn us_response(postal_code: Option<&str>) -> locations::Response {
+        locations::Response {
+            data: vec![locations::Location {
+                id: 1234,
+                name: None,
+                addresses: vec![locations::Address {
+                    street: None,
+                    city: None,
+                    state: None,
+                    postal_code: postal_code.map(str::to_string),
+                    country_code: "US".to_string(),
+                    latitude: None,
+                    longitude: None,
+                }],
+            }],
+        }
+    }
+
+    #[test]
+    fn us_locations_missing_postal_code_filtered() {
+        // Act
+        let locations = get_locations(us_response(None), US_ONLY);
+
+        // Assert
+        assert!(locations.is_empty());
+    }
+
+    #[test]
+    fn us_locations_zip_plus_four_normalized() {
+        // Act
+        let locations = get_locations(us_response(Some("12345-6789")), US_ONLY);
+
+        // Assert
+        assert_eq!(locations.len(), 1);
+        assert_eq!(locations[0].zip_code, "12345");
+    }
+
+    #[test]
+    fn us_locations_invalid_postal_code_filtered() {
+        // Act
+        let multibyte = get_locations(us_response(Some("１２３４５-６７８９")), US_ONLY);
+        let garbage = get_locations(us_response(Some("n/a")), US_ONLY);
+
+        // Assert
+        assert!(multibyte.is_empty());
+        assert!(garbage.is_empty());
+    }
+
+    #[test]
+    fn us_locations_no_addresses_filtered() {
+        // Arrange
+        let response = locations::Response {
+            data: vec![locations::Location {
+                id: 1234,
+                name: None,
+                addresses: vec![],
+            }],
+        };
+
+        // Act
+        let locations = get_locations(response, US_ONLY);
+
+        // Assert
+        assert!(locations.is_empty());
+    }
+
+    proptest! {
+        #[test]
+        fn parse_arbitrary_body_never_panics(body in ".*") {
+            if let Ok(response) = serde_json::from_str::<locations::Response>(&body) {
+                get_locations(response, US_ONLY);
+            }
+        }
+
+        #[test]
+        fn us_locations_arbitrary_address_never_panics(
+            id in any::<i32>(),
+            postal_code in proptest::option::of(".*"),
+            country_code in prop_oneof![Just("US".to_string()), ".*"],
+        ) {
+            let response = locations::Response {
+                data: vec![locations::Location {
+                    id,
+                    name: None,
+                    addresses: vec![locations::Address {
+                        street: None,
+                        city: None,
+                        state: None,
+                        postal_code,
+                        country_code,
+                        latitude: None,
+                        longitude: None,
+                    }],
+                }],
+            };
+            for location in get_locations(response, US_ONLY) {
+                prop_assert!(location.zip_code.as_str().parse::<ZipCode>().is_ok());
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn save_invalid_path() {
+        // Arrange
+        let locations = Locations(vec![]);
+
+        // Act
+        let save_result = locations
+            .save_with_retries("totally_nonexistent_dir/locations.json", 3)
+            .await;
+
+        // Assert
+        let error = save_result.unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("totally_nonexistent_dir/locations.json"));
+        assert!(matches!(error, SaveError::WriteError { .. }));
+    }
+
+    #[test]
+    fn effective_zip_overridden() {
+        // Arrange
+        let location = Location {
+            id: LocationId(3065),
+            zip_code: "75000".parse().unwrap(),
+            ..Default::default()
+        };
+
+        // Act
+        let zip_code = location.effective_zip(&default_zip_code_overrides());
+
+        // Assert
+        assert_eq!(zip_code, "75235");
+    }
+
+    #[test]
+    fn effective_zip_not_overridden() {
+        // Arrange
+        let location = Location {
+            id: LocationId(1234),
+            zip_code: "12345".parse().unwrap(),
+            ..Default::default()
+        };
+        let overrides = HashMap::from([(LocationId(3065), "75235-1234".parse().unwrap())]);
+
+        // Act
+        let zip_code = location.effective_zip(&overrides);
+
+        // Assert
+        assert_eq!(zip_code, "12345");
+    }
+
+    #[test]
+    fn index_by_zip_groups_shared_zip() {
+        // Arrange
+        let locations = Locations::from(vec![
+            location(1, "12345"),
+            location(2, "54321"),
+            location(3, "12345"),
+        ]);
+
+        // Act
+        let by_zip: Vec<i32> = locations.by_zip("12345").iter().map(|l| l.id.0).collect();
+        let index = locations.index_by_zip();
+
+        // Assert
+        assert_eq!(by_zip, vec![1, 3]);
+        let ids: Vec<i32> = index["12345"].iter().map(|l| l.id.0).collect();
+        assert_eq!(ids, vec![1, 3]);
+        assert_eq!(index["54321"].len(), 1);
+    }
+
+    #[test]
+    fn vec_round_trip() {
+        // Arrange
+        let fake_locations = vec![
+            Location {
+                id: LocationId(12345),
+                zip_code: "54321".parse().unwrap(),
+                ..Default::default()
+            },
+            Location {
+                id: LocationId(67890),
+                zip_code: "09876".parse().unwrap(),
+                ..Default::default()
+            },
+        ];
+
+        // Act
+        let locations = Locations::from(fake_locations);
+        let round_tripped: Vec<Location> = locations.into();
+
+        // Assert
+        assert_eq!(round_tripped.len(), 2);
+        assert_eq!(round_tripped[0].id, LocationId(12345));
+        assert_eq!(round_tripped[1].zip_code, "09876");
+    }
+
+    #[test]
+    fn to_csv_quotes_and_blanks() {
+        // Arrange
+        let locations = Locations(vec![
+            Location {
+                id: LocationId(1234),
+                zip_code: "12345".parse().unwrap(),
+                name: Some("Main St, Downtown".to_string()),
+                state: Some("TX".to_string()),
+                ..Default::default()
+            },
+            Location {
+                id: LocationId(5678),
+                zip_code: "56789".parse().unwrap(),
+                ..Default::default()
+            },
+        ]);
+
+        // Act
+        let output = locations.to_csv();
+
+        // Assert
+        let mut reader = csv::Reader::from_reader(output.as_bytes());
+        assert_eq!(reader.headers().unwrap(), LOCATION_CSV_HEADER.as_slice());
+        let rows: Vec<_> = reader.records().map(Result::unwrap).collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(&rows[0][2], "Main St, Downtown");
+        assert_eq!(&rows[0][5], "TX");
+        assert_eq!(&rows[1][0], "5678");
+        assert_eq!(&rows[1][2], "");
+    }
+
+    #[test]
+    fn to_geojson_skips_missing_coordinates() {
+        // Arrange
+        let locations = Locations(vec![
+            Location {
+                id: LocationId(1234),
+                zip_code: "12345".parse().unwrap(),
+                lat: Some(39.78),
+                lon: Some(-89.65),
+                ..Default::default()
+            },
+            Location {
+                id: LocationId(5678),
+                zip_code: "56789".parse().unwrap(),
+                lat: Some(40.0),
+                ..Default::default()
+            },
+        ]);
+
+        // Act
+        let geojson = locations.to_geojson();
+
+        // Assert
+        assert_eq!(
+            geojson,
+            json!({
+                "type": "FeatureCollection",
+                "features": [{
+                    "type": "Feature",
+                    "geometry": {"type": "Point", "coordinates": [-89.65, 39.78]},
+                    "properties": {"id": 1234, "zip_code": "12345"}
+                }]
+            })
+        );
+        let skipped: Vec<_> = locations
+            .without_coordinates()
+            .map(|location| location.id)
+            .collect();
+        assert_eq!(skipped, vec![LocationId(5678)]);
+    }
+
+    #[test]
+    fn in_state_ignores_case_and_counts_missing() {
+        // Arrange
+        let in_state = |id: i32, state: Option<&str>| Location {
+            state: state.map(str::to_string),
+            ..location(id, "12345")
+        };
+        let locations = Locations(vec![
+            in_state(1, Some("TX")),
+            in_state(2, Some("CA")),
+            in_state(3, None),
+            in_state(4, Some("tx")),
+        ]);
+
+        // Act
+        let (filtered, missing_state) = locations.in_state("Tx");
+
+        // Assert
+        let ids: Vec<i32> = filtered.into_iter().map(|location| location.id.0).collect();
+        assert_eq!(ids, vec![1, 4]);
+        assert_eq!(missing_state, 1);
+    }
+
+    #[test]
+    fn haversine_known_distance() {
+        // Act
+        let distance = haversine_miles((40.7128, -74.0060), (34.0522, -118.2437));
+
+        // Assert
+        assert!(
+            (distance - 2445.0).abs() < 5.0,
+            "unexpected distance {distance}"
+        );
+        assert_eq!(haversine_miles((40.0, -74.0), (40.0, -74.0)), 0.0);
+    }
+
+    #[test]
+    fn location_filter_combines_state_and_radius() {
+        // Arrange
+        let placed = |id: i32, state: &str, coordinates: Option<(f64, f64)>| Location {
+            state: Some(state.to_string()),
+            lat: coordinates.map(|(lat, _)| lat),
+            lon: coordinates.map(|(_, lon)| lon),
+            ..location(id, "12345")
+        };
+        let locations = Locations(vec![
+            // Downtown Kansas City, MO
+            placed(1, "MO", Some((39.0997, -94.5786))),
+            // Kansas City, KS, a few miles away but across the state line
+            placed(2, "KS", Some((39.1141, -94.6275))),
+            // St. Louis, MO
+            placed(3, "MO", Some((38.6270, -90.1994))),
+            placed(4, "mo", None),
+            // Independence, MO
+            placed(5, "mo", Some((39.0911, -94.4155))),
+        ]);
+
+        // Act
+        let filtered = LocationFilter::new()
+            .state("MO")
+            .within(39.0997, -94.5786, 25.0)
+            .apply(locations);
+
+        // Assert
+        let ids: Vec<i32> = filtered.into_iter().map(|location| location.id.0).collect();
+        assert_eq!(ids, vec![1, 5]);
+    }
+
+    #[test]
+    fn location_filter_matches_any_zip() {
+        // Arrange
+        let locations = Locations(vec![
+            location(1, "11111"),
+            location(2, "22222"),
+            location(3, "33333"),
+        ]);
+
+        // Act
+        let filtered = LocationFilter::new()
+            .zip("11111")
+            .unwrap()
+            .zips(&["33333-1234", "44444"])
+            .unwrap()
+            .apply(locations);
+        let invalid = LocationFilter::new().zips(&["22222", "2222"]);
+
+        // Assert
+        let ids: Vec<i32> = filtered.into_iter().map(|location| location.id.0).collect();
+        assert_eq!(ids, vec![1, 3]);
+        assert!(LocationFilter::new().matches(&location(2, "22222")));
+        assert_eq!(
+            invalid.unwrap_err(),
+            ZipCodeError::Invalid("2222".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn dedup_sorted_by_id() {
+        // Arrange
+        let locations = Locations(vec![
+            location(3, "33333"),
+            location(1, "11111"),
+            location(3, "99999"),
+            location(2, "22222"),
+            location(1, "99999"),
+        ]);
+        let server = MockServer::start_async().await;
+        server
+            .mock_async(|when, then| {
+                when.path("/");
+                then.status(200).json_body(json!({
+                    "data": [
+                        {"restaurantNumber": 3, "addresses": [{"postalCode": "33333", "countryCode": "US"}]},
+                        {"restaurantNumber": 1, "addresses": [{"postalCode": "11111", "countryCode": "US"}]},
+                        {"restaurantNumber": 3, "addresses": [{"postalCode": "99999", "countryCode": "US"}]},
+                        {"restaurantNumber": 2, "addresses": [{"postalCode": "22222", "countryCode": "US"}]}
+                    ]
+                }));
+            })
+            .await;
+        let url = server.url("/");
+
+        // Act
+        let deduped = locations.dedup_sorted();
+        let fetched = Locations::get_all_us_custom_with_retries(
+            &ApiKey::from_raw(FAKE_API_KEY),
+            &reqwest::Client::new(),
+            Some(url.as_str()),
+            1,
+            Duration::ZERO,
+        )
+        .await;
+
+        // Assert
+        let expected = vec![
+            location(1, "11111"),
+            location(2, "22222"),
+            location(3, "33333"),
+        ];
+        assert_eq!(deduped.0, expected);
+        assert_eq!(fetched.unwrap().0, expected);
+    }
+
+    #[tokio::test]
+    async fn get_one_found() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        let location_mock = server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/restaurant/1234")
+                    .header(API_KEY_HEADER, FAKE_API_KEY);
+                then.status(200).json_body(json!({
+                    "restaurantNumber": 1234,
+                    "restaurantName": "Main St",
+                    "addresses": [{"postalCode": "12345-6789", "countryCode": "US"}]
+                }));
+            })
+            .await;
+        let client = reqwest::Client::new();
+        let api_key = ApiKey::from_raw(FAKE_API_KEY);
+
+        // Act
+        let location = Locations::get_one(
+            &api_key,
+            &client,
+            LocationId(1234),
+            Some(&server.url("/restaurant/")),
+        )
+        .await;
+
+        // Assert
+        let location = location.unwrap().unwrap();
+        assert_eq!(location.id, LocationId(1234));
+        assert_eq!(location.zip_code, "12345");
+        assert_eq!(location.name.as_deref(), Some("Main St"));
+        location_mock.assert();
+    }
+
+    #[tokio::test]
+    async fn get_one_not_found() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        let location_mock = server
+            .mock_async(|when, then| {
+                when.path("/restaurant/9999");
+                then.status(404);
+            })
+            .await;
+        let client = reqwest::Client::new();
+        let api_key = ApiKey::from_raw(FAKE_API_KEY);
+
+        // Act
+        let location = Locations::get_one(
+            &api_key,
+            &client,
+            LocationId(9999),
+            Some(&server.url("/restaurant")),
+        )
+        .await;
+
+        // Assert
+        assert_eq!(location.unwrap(), None);
+        location_mock.assert();
+    }
 }
@@ -1,118 +1,859 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::Path,
+    sync::LazyLock,
+    time::Duration,
+};
+
 use derive_builder::Builder;
-use reqwest::Client;
-use serde::{Deserialize, Serialize};
+use regex::Regex;
+use reqwest::{
+    header::{HeaderMap, CONTENT_TYPE},
+    Client, StatusCode, Url,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize, Serializer};
+use tracing::Instrument;
+
+#[cfg(feature = "blocking")]
+use crate::util::{error_body_blocking, read_body_blocking, with_retries_blocking};
+use crate::{
+    api_interfaces::{catering as catering_api, menu},
+    constants::{DEFAULT_MAX_ATTEMPTS, DEFAULT_MAX_BODY_BYTES, DEFAULT_RETRY_BASE_DELAY},
+    error::{EndpointError, GetError, LoadError, SaveError},
+    locations::Location,
+    metrics::Metrics,
+    util::{default_http_client, error_body, read_body, with_retries},
+    ApiKey, ApiKeyPool, LocationId,
+};
 
-use crate::{api_interfaces::menu, error::GetError, util::default_http_client, ApiKey};
-use super::constants::API_KEY_HEADER;
+mod catalog;
+mod catering;
+mod diff;
+mod money;
+mod stats;
+#[cfg(test)]
+mod test_records;
+pub use crate::api_interfaces::menu::{Item, Response};
+pub use catalog::{item_catalog, CatalogItem};
+pub use catering::{CateringMenu, CateringPrice};
+pub use diff::{diff_menus, with_deltas, PriceChange, SummaryWithDelta};
+pub use money::Money;
+pub use stats::{
+    aggregate_by_zip, national_stats, price_spreads, BowlPriceStats, PriceSpread, ZipPriceStats,
+};
 
 const DEFAULT_MENU_SERVICE_URL_FORMAT: &str = 
 "https://services.chipotle.com/menuinnovation/v1/restaurants/$store/onlinemenu?channelId=web&includeUnavailableItems=true";
 
+/// The same service's catering channel, priced per person or per package.
+const DEFAULT_CATERING_MENU_URL_FORMAT: &str =
+"https://services.chipotle.com/menuinnovation/v1/restaurants/$store/onlinemenu?channelId=catering&includeUnavailableItems=true";
+
 pub const MENU_SERVICE_URL_REPLACE_TOKEN : &str = "$store";
 
-#[derive(Clone, Serialize, Deserialize, Debug)]
-pub struct Price {
-    pub normal_price: f32,
-    pub delivery_price: f32,
+static REPLACE_TOKEN_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\$[A-Za-z_][A-Za-z0-9_]*").expect("Invalid regex pattern"));
+
+/// A menu service URL template and the token in it that gets replaced with the restaurant ID.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Endpoint {
+    pub url: String,
+    pub replace_token: String,
 }
 
-impl PartialEq for Price {
-    fn eq(&self, other: &Self) -> bool {
-        let normal_price_within_epsilon =
-            (self.normal_price - other.normal_price).abs() < f32::EPSILON;
-        let delivery_price_within_epsilon =
-            (self.delivery_price - other.delivery_price).abs() < f32::EPSILON;
+impl Endpoint {
+    /// Create an endpoint from a URL template, detecting its `$`-prefixed replace token (e.g. `$store`).
+    pub fn from_template(url: &str) -> Result<Self, EndpointError> {
+        let mut tokens: Vec<&str> = REPLACE_TOKEN_REGEX
+            .find_iter(url)
+            .map(|m| m.as_str())
+            .collect();
+        tokens.dedup();
+        match tokens.as_slice() {
+            [token] => Ok(Self {
+                url: url.to_string(),
+                replace_token: token.to_string(),
+            }),
+            [] => Err(EndpointError::NoReplaceToken),
+            _ => Err(EndpointError::MultipleReplaceTokens(tokens.join(", "))),
+        }
+    }
+
+    /// Create an endpoint with an explicit replace token, which must appear in the URL as a whole token.
+    pub fn try_new(url: &str, replace_token: &str) -> Result<Self, EndpointError> {
+        let endpoint = Self {
+            url: url.to_string(),
+            replace_token: replace_token.to_string(),
+        };
+        if whole_token_starts(url, replace_token).is_empty() {
+            return Err(endpoint.token_not_found());
+        }
+        Ok(endpoint)
+    }
+
+    /// Use a URL template with the default `$store` token, or the default menu service URL if none is given.
+    pub(crate) fn with_default_token(url: Option<&str>) -> Self {
+        Self::with_default_token_or(url, DEFAULT_MENU_SERVICE_URL_FORMAT)
+    }
+
+    fn with_default_token_or(url: Option<&str>, default_url: &str) -> Self {
+        Self {
+            url: url.unwrap_or(default_url).to_string(),
+            replace_token: MENU_SERVICE_URL_REPLACE_TOKEN.to_string(),
+        }
+    }
+
+    /// Substitute the restaurant ID into the template and make sure the result is a valid URL.
+    /// Only whole tokens are replaced, so `$store` leaves `$store_id` alone. A template without the token is an error.
+    pub fn to_url(&self, restaurant_id: &LocationId) -> Result<Url, GetError> {
+        let starts = whole_token_starts(&self.url, &self.replace_token);
+        if starts.is_empty() {
+            return Err(GetError::BuildError(self.token_not_found().to_string()));
+        }
+        let restaurant_id = restaurant_id.to_string();
+        let mut url = String::with_capacity(self.url.len());
+        let mut last = 0;
+        for start in starts {
+            url.push_str(&self.url[last..start]);
+            url.push_str(&restaurant_id);
+            last = start + self.replace_token.len();
+        }
+        url.push_str(&self.url[last..]);
+        Url::parse(&url).map_err(|e| GetError::BuildError(format!("invalid menu URL {url:?}: {e}")))
+    }
+
+    fn token_not_found(&self) -> EndpointError {
+        EndpointError::ReplaceTokenNotInEndpoint {
+            token: self.replace_token.clone(),
+            url: self.url.clone(),
+        }
+    }
+}
 
-        normal_price_within_epsilon && delivery_price_within_epsilon
+/// Where `token` occurs in `haystack` without being part of a longer word.
+fn whole_token_starts(haystack: &str, token: &str) -> Vec<usize> {
+    if token.is_empty() {
+        return Vec::new();
     }
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    haystack
+        .match_indices(token)
+        .map(|(start, _)| start)
+        .filter(|&start| {
+            let end = start + token.len();
+            let bounded_before =
+                !token.starts_with(is_word) || !haystack[..start].ends_with(is_word);
+            let bounded_after = !token.ends_with(is_word) || !haystack[end..].starts_with(is_word);
+            bounded_before && bounded_after
+        })
+        .collect()
 }
 
-impl Eq for Price {}
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Price {
+    pub normal_price: Money,
+    pub delivery_price: Money,
+    /// Calories in the item, if the menu service lists them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub calories: Option<u32>,
+}
 
 impl TryFrom<menu::Item> for Price {
-    type Error = ();
+    type Error = GetError;
 
-    fn try_from(item: menu::Item) -> Result<Self, ()> {
+    /// Rejects negative and non-finite prices, and rounds the rest to the nearest cent.
+    fn try_from(item: menu::Item) -> Result<Self, GetError> {
+        for price in [item.unit_price, item.unit_delivery_price] {
+            if !price.is_finite() || price < 0.0 {
+                return Err(GetError::InvalidPriceError(item.item_name, price));
+            }
+        }
         Ok(Self {
-            normal_price: item.unit_price,
-            delivery_price: item.unit_delivery_price,
+            normal_price: Money::from_dollars(item.unit_price.into()),
+            delivery_price: Money::from_dollars(item.unit_delivery_price.into()),
+            calories: item.calories,
         })
     }
 }
 
+/// Options controlling how a raw menu is summarized.
+#[derive(Builder, Clone, Debug, PartialEq, Eq)]
+#[builder(default)]
+pub struct MenuOptions {
+    /// Item types treated as bowls, compared case-insensitively. Localized menus may use other words.
+    #[builder(setter(into))]
+    pub bowl_keywords: Vec<String>,
+    /// Price items the store isn't currently selling. Available items are still preferred when both are listed.
+    pub include_unavailable: bool,
+    /// Largest response body read before failing with [`GetError::BodyTooLarge`], in bytes.
+    pub max_body_bytes: usize,
+}
+
+impl Default for MenuOptions {
+    fn default() -> Self {
+        Self {
+            bowl_keywords: vec!["bowl".to_string()],
+            include_unavailable: false,
+            max_body_bytes: DEFAULT_MAX_BODY_BYTES,
+        }
+    }
+}
+
+/// Bowl proteins picked out of the menu. Item names match by the words they contain, e.g. `Veggie (Vegan) Bowl` is `veggie`.
+pub const BOWL_PROTEINS: [&str; 7] = [
+    "veggie",
+    "chicken",
+    "steak",
+    "barbacoa",
+    "carnitas",
+    "chicken al pastor",
+    "sofritas",
+];
+
+/// One of the [`BOWL_PROTEINS`], for picking a bowl without spelling out its name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Protein {
+    Veggie,
+    Chicken,
+    Steak,
+    Barbacoa,
+    Carnitas,
+    ChickenAlPastor,
+    Sofritas,
+}
+
+impl Protein {
+    /// Every protein, in the order of [`BOWL_PROTEINS`].
+    pub const ALL: [Protein; 7] = [
+        Protein::Veggie,
+        Protein::Chicken,
+        Protein::Steak,
+        Protein::Barbacoa,
+        Protein::Carnitas,
+        Protein::ChickenAlPastor,
+        Protein::Sofritas,
+    ];
+
+    /// The protein's key in [`Menu::bowl_prices`], e.g. `chicken al pastor`.
+    pub fn as_str(self) -> &'static str {
+        BOWL_PROTEINS[self as usize]
+    }
+}
+
+impl std::fmt::Display for Protein {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 // TODO: Add more fields as needed
-#[derive(Builder, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Menu {
-    pub veggie_bowl_price: Price,
-    pub chicken_bowl_price: Price,
-    pub steak_bowl_price: Price,
+    /// Bowl prices keyed by protein (one of [`BOWL_PROTEINS`]). Proteins the store doesn't sell are missing.
+    pub bowl_prices: BTreeMap<String, Price>,
+    /// Side and drink prices keyed by lowercased item name, e.g. `chips & guacamole`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub sides: BTreeMap<String, Price>,
 }
 
 impl Menu {
+    /// Every item price in the menu, keyed by item name (e.g. `chicken_al_pastor_bowl` or `chips_&_guacamole`).
+    pub fn prices(&self) -> Vec<(String, &Price)> {
+        let bowls = self
+            .bowl_prices
+            .iter()
+            .map(|(protein, price)| (bowl_item_name(protein), price));
+        let sides = self
+            .sides
+            .iter()
+            .map(|(name, price)| (name.replace(' ', "_"), price));
+        bowls.chain(sides).collect()
+    }
+
+    /// The bowl price for a protein, if the store sells it.
+    pub fn bowl_price(&self, protein: &str) -> Option<&Price> {
+        self.bowl_prices.get(protein)
+    }
+
     /// Get the summarized menu from the menu service.
-    pub async fn get(restaurant_id: &i32, key: &ApiKey) -> Result<Self, GetError> {
+    pub async fn get(restaurant_id: &LocationId, key: &ApiKey) -> Result<Self, GetError> {
         let client = default_http_client();
         Self::get_custom(restaurant_id, key, &client, None).await
     }
 
     /// Get the summarized menu from the menu service with a custom HTTP client and endpoint.
-    pub async fn get_custom(restaurant_id: &i32, key: &ApiKey, client: &Client, endpoint: Option<&str>) -> Result<Self, GetError> {
-        let complete_endpoint = endpoint.unwrap_or(DEFAULT_MENU_SERVICE_URL_FORMAT)
-            .replace(MENU_SERVICE_URL_REPLACE_TOKEN, &restaurant_id.to_string());
-        let response = client.get(&complete_endpoint)
-            .header(API_KEY_HEADER, key.get())
-            .send()
+    /// Server errors, rate limits and timeouts are retried with the default backoff.
+    pub async fn get_custom(
+        restaurant_id: &LocationId,
+        key: &ApiKey,
+        client: &Client,
+        endpoint: Option<&str>,
+    ) -> Result<Self, GetError> {
+        Self::get_custom_with_retries(
+            restaurant_id,
+            key,
+            client,
+            endpoint,
+            DEFAULT_MAX_ATTEMPTS,
+            DEFAULT_RETRY_BASE_DELAY,
+        )
+        .await
+    }
+
+    /// Get the summarized menu, making up to `max_attempts` attempts with exponential backoff from `base_delay`.
+    pub async fn get_custom_with_retries(
+        restaurant_id: &LocationId,
+        key: &ApiKey,
+        client: &Client,
+        endpoint: Option<&str>,
+        max_attempts: u32,
+        base_delay: Duration,
+    ) -> Result<Self, GetError> {
+        let endpoint = Endpoint::with_default_token(endpoint);
+        with_retries(max_attempts, base_delay, || {
+            Self::get_with_endpoint(restaurant_id, key, client, &endpoint)
+        })
+        .await
+    }
+
+    /// Get the full menu, every entree and side as the menu service returned it, instead of a summary.
+    /// Retries like [`Menu::get_custom`].
+    pub async fn get_raw(
+        restaurant_id: &LocationId,
+        key: &ApiKey,
+        client: &Client,
+        endpoint: Option<&str>,
+    ) -> Result<Response, GetError> {
+        let endpoint = Endpoint::with_default_token(endpoint);
+        let metrics = Metrics::default();
+        with_retries(DEFAULT_MAX_ATTEMPTS, DEFAULT_RETRY_BASE_DELAY, || {
+            Self::get_json_with_metrics(
+                restaurant_id,
+                key,
+                client,
+                &endpoint,
+                DEFAULT_MAX_BODY_BYTES,
+                &metrics,
+            )
+        })
+        .await
+    }
+
+    /// Get a store's catering prices, from the menu service's catering channel unless another endpoint is given.
+    /// The endpoint uses the `$store` replace token, and requests are retried like [`Menu::get_custom`].
+    pub async fn get_catering(
+        restaurant_id: &LocationId,
+        key: &ApiKey,
+        client: &Client,
+        endpoint: Option<&str>,
+    ) -> Result<CateringMenu, GetError> {
+        let endpoint = Endpoint::with_default_token_or(endpoint, DEFAULT_CATERING_MENU_URL_FORMAT);
+        let metrics = Metrics::default();
+        let response: catering_api::Response =
+            with_retries(DEFAULT_MAX_ATTEMPTS, DEFAULT_RETRY_BASE_DELAY, || {
+                Self::get_json_with_metrics(
+                    restaurant_id,
+                    key,
+                    client,
+                    &endpoint,
+                    DEFAULT_MAX_BODY_BYTES,
+                    &metrics,
+                )
+            })
             .await?;
-        if !response.status().is_success() {
-            return Err(GetError::ResponseError(response.status()));
+        Ok(CateringMenu::from(response))
+    }
+
+    /// Get a menu service response once, counting the request in `metrics`.
+    async fn get_json_with_metrics<T: DeserializeOwned>(
+        restaurant_id: &LocationId,
+        key: &ApiKey,
+        client: &Client,
+        endpoint: &Endpoint,
+        max_body_bytes: usize,
+        metrics: &Metrics,
+    ) -> Result<T, GetError> {
+        let url = endpoint.to_url(restaurant_id)?;
+        let span = tracing::debug_span!("menu_request", %url, store_id = %restaurant_id);
+        async {
+            let response = key
+                .authorize(client.get(url.clone()))
+                .send()
+                .await
+                .inspect_err(|_| metrics.record_request_error())?;
+            tracing::debug!(status = %response.status(), "menu response");
+            metrics.record_response(response.status());
+            if !response.status().is_success() {
+                return Err(GetError::ResponseError {
+                    status: response.status(),
+                    body: error_body(response).await,
+                });
+            }
+            let content_type = content_type(response.headers());
+            let body = read_body(response, max_body_bytes).await?;
+            metrics.record_bytes(body.len());
+            parse_response_body(content_type, &body)
+        }
+        .instrument(span)
+        .await
+    }
+
+    /// Get the summarized menu from a menu service endpoint template.
+    pub async fn get_with_endpoint(
+        restaurant_id: &LocationId,
+        key: &ApiKey,
+        client: &Client,
+        endpoint: &Endpoint,
+    ) -> Result<Self, GetError> {
+        Self::get_with_options(
+            restaurant_id,
+            key,
+            client,
+            endpoint,
+            &MenuOptions::default(),
+        )
+        .await
+    }
+
+    /// Get the summarized menu from a menu service endpoint template, summarizing with custom options.
+    pub async fn get_with_options(
+        restaurant_id: &LocationId,
+        key: &ApiKey,
+        client: &Client,
+        endpoint: &Endpoint,
+        options: &MenuOptions,
+    ) -> Result<Self, GetError> {
+        Self::get_with_metrics(
+            restaurant_id,
+            key,
+            client,
+            endpoint,
+            options,
+            &Metrics::default(),
+        )
+        .await
+    }
+
+    /// Like [`Menu::get_with_options`], counting the request in `metrics`.
+    pub(crate) async fn get_with_metrics(
+        restaurant_id: &LocationId,
+        key: &ApiKey,
+        client: &Client,
+        endpoint: &Endpoint,
+        options: &MenuOptions,
+        metrics: &Metrics,
+    ) -> Result<Self, GetError> {
+        let response = Self::get_json_with_metrics(
+            restaurant_id,
+            key,
+            client,
+            endpoint,
+            options.max_body_bytes,
+            metrics,
+        )
+        .await?;
+        Menu::summarize(response, options)
+    }
+
+    /// Like [`Menu::get_with_metrics`], but keeping the raw response the summary was made from.
+    pub(crate) async fn get_full_with_metrics(
+        restaurant_id: &LocationId,
+        key: &ApiKey,
+        client: &Client,
+        endpoint: &Endpoint,
+        options: &MenuOptions,
+        metrics: &Metrics,
+    ) -> Result<FullMenu, GetError> {
+        let response: Response = Self::get_json_with_metrics(
+            restaurant_id,
+            key,
+            client,
+            endpoint,
+            options.max_body_bytes,
+            metrics,
+        )
+        .await?;
+        let summary = Menu::summarize(response.clone(), options)?;
+        Ok(FullMenu { summary, response })
+    }
+
+    /// Get the summarized menu with a blocking HTTP client, without needing an async runtime.
+    /// Behaves like [`Menu::get_custom`], retrying with the default backoff.
+    #[cfg(feature = "blocking")]
+    pub fn get_custom_blocking(
+        restaurant_id: &LocationId,
+        key: &ApiKey,
+        client: &reqwest::blocking::Client,
+        endpoint: Option<&str>,
+    ) -> Result<Self, GetError> {
+        let endpoint = Endpoint::with_default_token(endpoint);
+        let url = endpoint.to_url(restaurant_id)?;
+        let _span = tracing::debug_span!("menu_request", %url, store_id = %restaurant_id).entered();
+        with_retries_blocking(DEFAULT_MAX_ATTEMPTS, DEFAULT_RETRY_BASE_DELAY, || {
+            let response = key.authorize_blocking(client.get(url.clone())).send()?;
+            tracing::debug!(status = %response.status(), "menu response");
+            if !response.status().is_success() {
+                return Err(GetError::ResponseError {
+                    status: response.status(),
+                    body: error_body_blocking(response),
+                });
+            }
+            let options = MenuOptions::default();
+            let content_type = content_type(response.headers());
+            let body = read_body_blocking(response, options.max_body_bytes)?;
+            Menu::summarize(parse_response_body(content_type, &body)?, &options)
+        })
+    }
+
+    /// Get the summarized menu, trying each endpoint in order.
+    /// The next endpoint is only tried if the previous one failed with a server error or timed out.
+    /// Each endpoint is tried once, without retries.
+    pub async fn get_with_fallbacks(
+        restaurant_id: &LocationId,
+        key: &ApiKey,
+        client: &Client,
+        endpoints: &[Endpoint],
+    ) -> Result<Self, GetError> {
+        let mut last_error = None;
+        for endpoint in endpoints {
+            match Self::get_with_endpoint(restaurant_id, key, client, endpoint).await {
+                Err(e) if e.is_server_failure() => last_error = Some(e),
+                result => return result,
+            }
+        }
+        Err(last_error
+            .unwrap_or_else(|| GetError::BuildError("no menu endpoints provided".to_string())))
+    }
+
+    /// Get the summarized menu using the next key from a pool of API keys.
+    /// Keys rejected with a 403 are dropped from the pool and the request is retried with the next key.
+    pub async fn get_with_pool(
+        restaurant_id: &LocationId,
+        pool: &ApiKeyPool,
+        client: &Client,
+        endpoint: Option<&str>,
+    ) -> Result<Self, GetError> {
+        let mut last_error = None;
+        while let Some(key) = pool.next_key() {
+            match Self::get_custom(restaurant_id, &key, client, endpoint).await {
+                Err(
+                    e @ GetError::ResponseError {
+                        status: StatusCode::FORBIDDEN,
+                        ..
+                    },
+                ) => {
+                    pool.remove(&key);
+                    last_error = Some(e);
+                }
+                result => return result,
+            }
         }
-        let body = response.text().await.map_err(GetError::ResponseBodyError)?;
-        let parsed_body: menu::Response = serde_json::from_str(&body)?;
-        Menu::try_from(parsed_body)
+        Err(last_error.unwrap_or_else(|| GetError::BuildError("no API keys available".to_string())))
     }
+}
+
+/// A store's summarized menu together with the raw response, and so every item, it was summarized from.
+#[derive(Clone, Debug)]
+pub struct FullMenu {
+    pub summary: Menu,
+    pub response: Response,
+}
+
+/// Serializes a [`Menu`] with every one of the [`BOWL_PROTEINS`] in `bowl_prices`, `null` for those the store
+/// doesn't sell, so every store's record has the same shape. Sides have no fixed list and stay as they are.
+#[derive(Clone, Copy, Debug)]
+pub struct DenseMenu<'a>(pub &'a Menu);
+
+impl Serialize for DenseMenu<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Dense<'a> {
+            bowl_prices: BTreeMap<&'a str, Option<&'a Price>>,
+            #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+            sides: &'a BTreeMap<String, Price>,
+        }
 
+        let mut bowl_prices: BTreeMap<&str, Option<&Price>> = BOWL_PROTEINS
+            .iter()
+            .map(|protein| (*protein, None))
+            .collect();
+        for (protein, price) in &self.0.bowl_prices {
+            bowl_prices.insert(protein, Some(price));
+        }
+        Dense {
+            bowl_prices,
+            sides: &self.0.sides,
+        }
+        .serialize(serializer)
+    }
 }
 
 impl TryFrom<menu::Response> for Menu {
     type Error = GetError;
 
     fn try_from(response: menu::Response) -> Result<Self, GetError> {
-        let mut builder = MenuBuilder::default();
+        Menu::summarize(response, &MenuOptions::default())
+    }
+}
+
+/// The response's `Content-Type`, if it has a readable one.
+fn content_type(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+/// Parse a menu service response body, first making sure it's JSON so that a block page isn't reported as malformed data.
+fn parse_response_body<T: DeserializeOwned>(
+    content_type: Option<String>,
+    body: &str,
+) -> Result<T, GetError> {
+    let json_content_type = content_type
+        .as_deref()
+        .is_none_or(|content_type| content_type.to_lowercase().contains("json"));
+    if !json_content_type || !body.trim_start().starts_with('{') {
+        return Err(GetError::UnexpectedContentType {
+            content_type: content_type.unwrap_or_else(|| "no content type".to_string()),
+        });
+    }
+    Ok(serde_json::from_str(body)?)
+}
+
+/// The item name for a protein's bowl, e.g. `chicken al pastor` becomes `chicken_al_pastor_bowl`.
+pub fn bowl_item_name(protein: &str) -> String {
+    format!("{}_bowl", protein.replace(' ', "_"))
+}
+
+/// Words or phrases that identify a protein in a bowl's name, checked in order so that
+/// `al pastor` is found before the `chicken` it also contains.
+const PROTEIN_ALIASES: [(&str, &str); 9] = [
+    ("al pastor", "chicken al pastor"),
+    ("sofritas", "sofritas"),
+    ("veggie", "veggie"),
+    ("vegetarian", "veggie"),
+    ("barbacoa", "barbacoa"),
+    ("carnitas", "carnitas"),
+    ("chicken", "chicken"),
+    ("steak", "steak"),
+    ("carne asada", "steak"),
+];
+
+/// Normalize an item name by lowercasing it, collapsing whitespace and dropping the bowl keyword and "burrito",
+/// e.g. `Chicken  Burrito Bowl` becomes `chicken`.
+fn normalize_item_name(item_name: &str, keyword: &str) -> String {
+    let keyword = keyword.to_lowercase();
+    item_name
+        .to_lowercase()
+        .split_whitespace()
+        .filter(|token| *token != keyword && *token != "burrito")
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The protein (one of [`BOWL_PROTEINS`]) a bowl's name refers to, found by the first alias it contains
+/// as whole words, e.g. `Veggie (Vegan) Bowl` is `veggie`.
+fn protein_name(item_name: &str, keyword: &str) -> Option<&'static str> {
+    let padded = format!(
+        " {} ",
+        normalize_item_name(&item_name.replace(['(', ')', ',', '-'], " "), keyword)
+    );
+    PROTEIN_ALIASES
+        .iter()
+        .find(|(alias, _)| padded.contains(&format!(" {} ", alias)))
+        .map(|(_, protein)| *protein)
+}
+
+impl Menu {
+    fn summarize(response: menu::Response, options: &MenuOptions) -> Result<Self, GetError> {
+        let mut bowl_prices = BTreeMap::new();
+        // Proteins priced from an unavailable item, to be replaced if an available one turns up
+        let mut unavailable_picks = HashSet::new();
 
         for entree in response.entrees {
-            if builder.chicken_bowl_price.is_some()
-                && builder.veggie_bowl_price.is_some()
-                && builder.steak_bowl_price.is_some()
-            {
+            if bowl_prices.len() == BOWL_PROTEINS.len() && unavailable_picks.is_empty() {
                 break;
             }
-            if entree.item_type.to_lowercase() != "bowl" {
+            let item_type = entree.item_type.to_lowercase();
+            let Some(keyword) = options
+                .bowl_keywords
+                .iter()
+                .find(|keyword| keyword.to_lowercase() == item_type)
+            else {
                 continue;
-            }
-            let item_name = entree.item_name.clone();
-            let price = Price::try_from(entree).expect("Failed to convert entree to price");
-            match item_name.to_lowercase().replace("bowl", "").trim() {
-                "veggie" => {
-                    builder.veggie_bowl_price(price);
-                }
-                "chicken" => {
-                    builder.chicken_bowl_price(price);
-                }
-                "steak" => {
-                    builder.steak_bowl_price(price);
+            };
+            let Some(protein) = protein_name(&entree.item_name, keyword).map(str::to_string) else {
+                continue;
+            };
+            if entree.is_available {
+                // With names matched loosely, the first available bowl for a protein is kept
+                if bowl_prices.contains_key(&protein) && !unavailable_picks.contains(&protein) {
+                    continue;
                 }
-                _ => {}
+                unavailable_picks.remove(&protein);
+            } else if options.include_unavailable && !bowl_prices.contains_key(&protein) {
+                unavailable_picks.insert(protein.clone());
+            } else {
+                continue;
             }
+            bowl_prices.insert(protein, Price::try_from(entree)?);
         }
-        Ok(builder.build()?)
+        if bowl_prices.is_empty() {
+            return Err(GetError::TranslateError(
+                "no bowls found on the menu".to_string(),
+            ));
+        }
+        // Sides were ignored before, so one with a bad price is skipped rather than failing the whole menu.
+        // Unavailable sides go first so an available one with the same name overwrites it
+        let (available_sides, unavailable_sides): (Vec<_>, Vec<_>) = response
+            .sides
+            .into_iter()
+            .partition(|side| side.is_available);
+        let unavailable_sides = unavailable_sides
+            .into_iter()
+            .filter(|_| options.include_unavailable);
+        let sides = unavailable_sides
+            .chain(available_sides)
+            .filter_map(|side| {
+                let name = side.item_name.to_lowercase().split_whitespace().collect::<Vec<_>>().join(" ");
+                match Price::try_from(side) {
+                    Ok(price) => Some((name, price)),
+                    Err(e) => {
+                        tracing::debug!(side = %name, error = %e, "skipping side with an invalid price");
+                        None
+                    }
+                }
+            })
+            .collect();
+        Ok(Self { bowl_prices, sides })
+    }
+}
+
+impl Menu {
+    pub async fn load<P: AsRef<Path>>(path: P) -> Result<Self, LoadError> {
+        load_json(path.as_ref()).await
+    }
+
+    pub async fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), SaveError> {
+        save_json(path.as_ref(), self).await
+    }
+}
+
+/// Menus alongside the locations they were fetched for, e.g. to checkpoint a crawl and re-analyze it offline.
+/// Serializes as a list of `{"location": ..., "menu": ...}` records, like `burritocli get-all-menus`.
+#[derive(Debug, Default, PartialEq)]
+pub struct Menus(Vec<(Location, Menu)>);
+
+/// A store and its menu: the shape of each record `burritocli get-all-menus` writes and [`Menus`] saves.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct MenuRecord {
+    pub location: Location,
+    pub menu: Menu,
+}
+
+/// The JSON Schema of a [`MenuRecord`], as a contract for consumers of crawl output.
+#[cfg(feature = "schema")]
+pub fn menu_record_schema() -> serde_json::Value {
+    schemars::schema_for!(MenuRecord).to_value()
+}
+
+/// Borrowed [`MenuRecord`], so saving doesn't need to clone every menu.
+#[derive(Serialize)]
+struct MenuRecordRef<'a> {
+    location: &'a Location,
+    menu: &'a Menu,
+}
+
+impl Menus {
+    pub async fn load<P: AsRef<Path>>(path: P) -> Result<Self, LoadError> {
+        let records: Vec<MenuRecord> = load_json(path.as_ref()).await?;
+        Ok(Self(
+            records
+                .into_iter()
+                .map(|record| (record.location, record.menu))
+                .collect(),
+        ))
+    }
+
+    pub async fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), SaveError> {
+        let records: Vec<_> = self
+            .0
+            .iter()
+            .map(|(location, menu)| MenuRecordRef { location, menu })
+            .collect();
+        save_json(path.as_ref(), &records).await
+    }
+
+    /// Annotate these freshly fetched menus with their price changes since the dump saved at `previous`.
+    pub async fn with_deltas_since<P: AsRef<Path>>(
+        self,
+        previous: P,
+    ) -> Result<Vec<SummaryWithDelta>, LoadError> {
+        let previous = Self::load(previous).await?;
+        Ok(with_deltas(&previous.0, self.0))
+    }
+}
+
+impl From<Vec<(Location, Menu)>> for Menus {
+    fn from(menus: Vec<(Location, Menu)>) -> Self {
+        Self(menus)
+    }
+}
+
+impl From<Menus> for Vec<(Location, Menu)> {
+    fn from(menus: Menus) -> Self {
+        menus.0
+    }
+}
+
+impl IntoIterator for Menus {
+    type Item = (Location, Menu);
+    type IntoIter = std::vec::IntoIter<(Location, Menu)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
     }
 }
 
+async fn load_json<T: DeserializeOwned>(path: &Path) -> Result<T, LoadError> {
+    let file_contents =
+        tokio::fs::read_to_string(path)
+            .await
+            .map_err(|source| LoadError::ReadError {
+                path: path.to_path_buf(),
+                source,
+            })?;
+    serde_json::from_str(&file_contents).map_err(|source| LoadError::ParseError {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+async fn save_json<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<(), SaveError> {
+    let serialized = serde_json::to_string(value).map_err(|source| SaveError::SerializeError {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    tokio::fs::write(path, serialized)
+        .await
+        .map_err(|source| SaveError::WriteError {
+            path: path.to_path_buf(),
+            source,
+        })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::constants::API_KEY_HEADER;
     use httpmock::prelude::*;
+    use proptest::prelude::*;
     use serde_json::json;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
 
     const FAKE_API_KEY: &str = "fake_api_key";
 
@@ -153,33 +894,1362 @@ mod tests {
 
         let menu_mock = server
             .mock_async(|when, then| {
-                when.path("/").header(API_KEY_HEADER, FAKE_API_KEY);
+                when.path("/1234").header(API_KEY_HEADER, FAKE_API_KEY);
                 then.status(200).json_body(response_json);
             })
             .await;
-        let url = server.url("/");
+        let url = server.url("/$store");
         let client = reqwest::Client::new();
         let api_key = ApiKey::from_raw(FAKE_API_KEY);
 
         // Act
-        let menu = Menu::get_custom(&1234, &api_key, &client, Some(url.as_str())).await;
+        let menu = Menu::get_custom(&LocationId(1234), &api_key, &client, Some(url.as_str())).await;
 
         // Assert
         assert!(menu.is_ok(), "Failed to get menu: {:?}", menu.unwrap_err());
         let menu = menu.unwrap();
-        assert_eq!(menu.veggie_bowl_price, Price {
-            normal_price: 7.99,
-            delivery_price: 8.99,
-        });
-        assert_eq!(menu.chicken_bowl_price, Price {
-            normal_price: 8.99,
-            delivery_price: 9.99,
-        });
-        assert_eq!(menu.steak_bowl_price, Price {
-            normal_price: 9.99,
-            delivery_price: 10.99,
-        });
+        assert_eq!(
+            menu.bowl_price("veggie"),
+            Some(&Price {
+                normal_price: Money::from_cents(799),
+                delivery_price: Money::from_cents(899),
+                calories: None,
+            })
+        );
+        assert_eq!(
+            menu.bowl_price("chicken"),
+            Some(&Price {
+                normal_price: Money::from_cents(899),
+                delivery_price: Money::from_cents(999),
+                calories: None,
+            })
+        );
+        assert_eq!(
+            menu.bowl_price("steak"),
+            Some(&Price {
+                normal_price: Money::from_cents(999),
+                delivery_price: Money::from_cents(1099),
+                calories: None,
+            })
+        );
         menu_mock.assert();
     }
-    
-}
\ No newline at end of file
+
+    fn arbitrary_item() -> impl Strategy<Value = menu::Item> {
+        (
+            ".*",
+            prop_oneof![Just("Bowl".to_string()), ".*"],
+            prop_oneof![Just("Chicken Bowl".to_string()), ".*"],
+            any::<f32>(),
+            any::<f32>(),
+        )
+            .prop_map(
+                |(item_id, item_type, item_name, unit_price, unit_delivery_price)| menu::Item {
+                    item_category: "Entree".to_string(),
+                    item_type,
+                    item_id,
+                    item_name,
+                    unit_price,
+                    unit_delivery_price,
+                    is_available: true,
+                    calories: None,
+                },
+            )
+    }
+
+    fn bowl(item_name: &str, unit_price: f32) -> menu::Item {
+        menu::Item {
+            item_category: "Entree".to_string(),
+            item_type: "Bowl".to_string(),
+            item_id: item_name.to_string(),
+            item_name: item_name.to_string(),
+            unit_price,
+            unit_delivery_price: unit_price + 1.0,
+            is_available: true,
+            calories: None,
+        }
+    }
+
+    #[test]
+    fn price_rejects_negative() {
+        // Act
+        let price = Price::try_from(bowl("Chicken Bowl", -8.99));
+
+        // Assert
+        assert!(matches!(
+            price.unwrap_err(),
+            GetError::InvalidPriceError(_, _)
+        ));
+    }
+
+    #[test]
+    fn price_rejects_nan() {
+        // Act
+        let price = Price::try_from(bowl("Chicken Bowl", f32::NAN));
+
+        // Assert
+        assert!(matches!(
+            price.unwrap_err(),
+            GetError::InvalidPriceError(_, _)
+        ));
+    }
+
+    #[test]
+    fn translate_negative_price_is_error() {
+        // Arrange
+        let response = menu::Response {
+            restaurant_id: 1234,
+            entrees: vec![
+                bowl("Veggie Bowl", 7.99),
+                bowl("Chicken Bowl", -8.99),
+                bowl("Steak Bowl", 9.99),
+            ],
+            sides: vec![],
+        };
+
+        // Act
+        let menu = Menu::try_from(response);
+
+        // Assert
+        assert!(
+            matches!(menu.unwrap_err(), GetError::InvalidPriceError(name, _) if name == "Chicken Bowl")
+        );
+    }
+
+    #[test]
+    fn summarize_all_proteins() {
+        // Arrange
+        let response = menu::Response {
+            restaurant_id: 1234,
+            entrees: vec![
+                bowl("Barbacoa Bowl", 9.99),
+                bowl("Carnitas Bowl", 9.49),
+                bowl("Chicken Al Pastor Bowl", 9.29),
+                bowl("Sofritas Bowl", 8.49),
+                bowl("Lifestyle Bowl", 12.99),
+            ],
+            sides: vec![],
+        };
+
+        // Act
+        let menu = Menu::try_from(response);
+
+        // Assert
+        assert!(
+            menu.is_ok(),
+            "Failed to summarize menu: {:?}",
+            menu.unwrap_err()
+        );
+        let menu = menu.unwrap();
+        assert_eq!(menu.bowl_prices.len(), 4);
+        assert_eq!(
+            menu.bowl_price("chicken al pastor"),
+            Some(&Price {
+                normal_price: Money::from_cents(929),
+                delivery_price: Money::from_cents(1029),
+                calories: None,
+            })
+        );
+        assert_eq!(menu.bowl_price("steak"), None);
+        assert!(menu
+            .prices()
+            .iter()
+            .any(|(item, _)| item == "chicken_al_pastor_bowl"));
+    }
+
+    #[test]
+    fn summarize_sides() {
+        // Arrange
+        let side = |item_name: &str, unit_price: f32| menu::Item {
+            item_category: "Side".to_string(),
+            item_type: "Side".to_string(),
+            ..bowl(item_name, unit_price)
+        };
+        let response = menu::Response {
+            restaurant_id: 1234,
+            entrees: vec![bowl("Chicken Bowl", 8.99)],
+            sides: vec![
+                side("Chips &  Guacamole", 4.25),
+                side("Mexican Coca-Cola", 3.15),
+                side("Chips", -1.0),
+            ],
+        };
+
+        // Act
+        let menu = Menu::try_from(response);
+
+        // Assert
+        assert!(
+            menu.is_ok(),
+            "Failed to summarize menu: {:?}",
+            menu.unwrap_err()
+        );
+        let menu = menu.unwrap();
+        assert_eq!(menu.sides.len(), 2);
+        assert_eq!(
+            menu.sides["chips & guacamole"],
+            Price {
+                normal_price: Money::from_cents(425),
+                delivery_price: Money::from_cents(525),
+                calories: None,
+            }
+        );
+        assert!(menu
+            .prices()
+            .iter()
+            .any(|(item, _)| item == "chips_&_guacamole"));
+    }
+
+    #[test]
+    fn deserialize_without_sides() {
+        // Act
+        let menu: Menu = serde_json::from_value(json!({
+            "bowl_prices": {"chicken": {"normal_price": 8.99, "delivery_price": 9.99}}
+        }))
+        .unwrap();
+
+        // Assert
+        assert!(menu.sides.is_empty());
+    }
+
+    #[test]
+    fn dense_menu_serializes_missing_bowls_as_null() {
+        // Arrange
+        let menu = Menu {
+            bowl_prices: BTreeMap::from([(
+                "chicken".to_string(),
+                Price {
+                    normal_price: Money::from_cents(899),
+                    delivery_price: Money::from_cents(999),
+                    calories: None,
+                },
+            )]),
+            ..Default::default()
+        };
+
+        // Act
+        let sparse = serde_json::to_value(&menu).unwrap();
+        let dense = serde_json::to_value(DenseMenu(&menu)).unwrap();
+
+        // Assert
+        assert_eq!(sparse["bowl_prices"].as_object().unwrap().len(), 1);
+        let bowls = dense["bowl_prices"].as_object().unwrap();
+        assert_eq!(bowls.len(), BOWL_PROTEINS.len());
+        assert_eq!(bowls["chicken"], sparse["bowl_prices"]["chicken"]);
+        assert!(bowls["steak"].is_null());
+        assert!(bowls["chicken al pastor"].is_null());
+        assert_eq!(dense.get("sides"), sparse.get("sides"));
+    }
+
+    #[test]
+    fn protein_names_match_bowl_proteins() {
+        // Act
+        let names = Protein::ALL.map(Protein::as_str);
+
+        // Assert
+        assert_eq!(names, BOWL_PROTEINS);
+        assert_eq!(Protein::ChickenAlPastor.to_string(), "chicken al pastor");
+    }
+
+    #[test]
+    fn summarize_burrito_bowl_wording() {
+        // Arrange
+        let response = menu::Response {
+            restaurant_id: 1234,
+            entrees: vec![
+                bowl("Chicken Burrito Bowl", 8.99),
+                bowl("Chicken  Al Pastor Burrito Bowl", 9.29),
+            ],
+            sides: vec![],
+        };
+
+        // Act
+        let menu = Menu::try_from(response);
+
+        // Assert
+        assert!(
+            menu.is_ok(),
+            "Failed to summarize menu: {:?}",
+            menu.unwrap_err()
+        );
+        let menu = menu.unwrap();
+        assert_eq!(
+            menu.bowl_price("chicken"),
+            Some(&Price {
+                normal_price: Money::from_cents(899),
+                delivery_price: Money::from_cents(999),
+                calories: None,
+            })
+        );
+        assert!(menu.prices().iter().any(|(item, _)| item == "chicken_bowl"));
+        assert!(menu.bowl_price("chicken al pastor").is_some());
+    }
+
+    #[test]
+    fn summarize_bowl_names_with_descriptors() {
+        // Arrange
+        let response = menu::Response {
+            restaurant_id: 1234,
+            entrees: vec![
+                bowl("Veggie (Vegan) Bowl", 7.99),
+                bowl("Chicken Al Pastor Bowl", 9.29),
+                bowl("  CHICKEN   bowl ", 8.99),
+                bowl("Steak Bowl - Grilled", 9.99),
+                bowl("Sofritas Bowl, Spicy", 8.49),
+            ],
+            sides: vec![],
+        };
+
+        // Act
+        let menu = Menu::try_from(response);
+
+        // Assert
+        assert!(
+            menu.is_ok(),
+            "Failed to summarize menu: {:?}",
+            menu.unwrap_err()
+        );
+        let menu = menu.unwrap();
+        let cents = |protein: &str| menu.bowl_price(protein).map(|price| price.normal_price);
+        assert_eq!(cents("veggie"), Some(Money::from_cents(799)));
+        assert_eq!(cents("chicken al pastor"), Some(Money::from_cents(929)));
+        assert_eq!(cents("chicken"), Some(Money::from_cents(899)));
+        assert_eq!(cents("steak"), Some(Money::from_cents(999)));
+        assert_eq!(cents("sofritas"), Some(Money::from_cents(849)));
+    }
+
+    #[test]
+    fn summarize_keeps_first_bowl_matching_a_protein() {
+        // Arrange
+        let response = menu::Response {
+            restaurant_id: 1234,
+            entrees: vec![
+                bowl("Chicken Bowl", 8.99),
+                bowl("Double Chicken Bowl", 11.99),
+                bowl("Vegetarian Bowl", 7.99),
+            ],
+            sides: vec![],
+        };
+
+        // Act
+        let menu = Menu::try_from(response).unwrap();
+
+        // Assert
+        assert_eq!(
+            menu.bowl_price("chicken").map(|price| price.normal_price),
+            Some(Money::from_cents(899))
+        );
+        assert_eq!(
+            menu.bowl_price("veggie").map(|price| price.normal_price),
+            Some(Money::from_cents(799))
+        );
+    }
+
+    #[test]
+    fn summarize_localized_bowl_keyword() {
+        // Arrange
+        let localized_bowl = |item_name: &str, unit_price: f32| menu::Item {
+            item_type: "Bol".to_string(),
+            ..bowl(item_name, unit_price)
+        };
+        let response = menu::Response {
+            restaurant_id: 1234,
+            entrees: vec![
+                localized_bowl("Veggie Bol", 7.99),
+                localized_bowl("Chicken Bol", 8.99),
+                localized_bowl("Steak Bol", 9.99),
+            ],
+            sides: vec![],
+        };
+        let options = MenuOptionsBuilder::default()
+            .bowl_keywords(vec!["bowl".to_string(), "bol".to_string()])
+            .build()
+            .unwrap();
+
+        // Act
+        let menu = Menu::summarize(response, &options);
+
+        // Assert
+        assert!(
+            menu.is_ok(),
+            "Failed to summarize menu: {:?}",
+            menu.unwrap_err()
+        );
+        assert_eq!(
+            menu.unwrap().bowl_price("chicken"),
+            Some(&Price {
+                normal_price: Money::from_cents(899),
+                delivery_price: Money::from_cents(999),
+                calories: None,
+            })
+        );
+    }
+
+    #[test]
+    fn summarize_localized_bowl_keyword_not_configured() {
+        // Arrange
+        let response = menu::Response {
+            restaurant_id: 1234,
+            entrees: vec![menu::Item {
+                item_type: "Bol".to_string(),
+                ..bowl("Chicken Bol", 8.99)
+            }],
+            sides: vec![],
+        };
+
+        // Act
+        let menu = Menu::try_from(response);
+
+        // Assert
+        assert!(matches!(menu.unwrap_err(), GetError::TranslateError(_)));
+    }
+
+    #[test]
+    fn summarize_prefers_available_items() {
+        // Arrange
+        let unavailable = |item: menu::Item| menu::Item {
+            is_available: false,
+            ..item
+        };
+        let response = || menu::Response {
+            restaurant_id: 1234,
+            entrees: vec![
+                unavailable(bowl("Chicken Bowl", 7.99)),
+                bowl("Chicken Bowl", 8.99),
+                bowl("Steak Bowl", 9.99),
+                unavailable(bowl("Steak Bowl", 10.99)),
+                unavailable(bowl("Carnitas Bowl", 9.49)),
+            ],
+            sides: vec![
+                unavailable(bowl("Chips", 1.99)),
+                bowl("Chips", 2.19),
+                unavailable(bowl("Queso Blanco", 1.50)),
+            ],
+        };
+        let include_unavailable = MenuOptionsBuilder::default()
+            .include_unavailable(true)
+            .build()
+            .unwrap();
+
+        // Act
+        let available_only = Menu::try_from(response()).unwrap();
+        let with_unavailable = Menu::summarize(response(), &include_unavailable).unwrap();
+
+        // Assert
+        for menu in [&available_only, &with_unavailable] {
+            assert_eq!(
+                menu.bowl_price("chicken").unwrap().normal_price,
+                Money::from_cents(899)
+            );
+            assert_eq!(
+                menu.bowl_price("steak").unwrap().normal_price,
+                Money::from_cents(999)
+            );
+            assert_eq!(menu.sides["chips"].normal_price, Money::from_cents(219));
+        }
+        assert_eq!(available_only.bowl_price("carnitas"), None);
+        assert!(!available_only.sides.contains_key("queso blanco"));
+        assert_eq!(
+            with_unavailable
+                .bowl_price("carnitas")
+                .unwrap()
+                .normal_price,
+            Money::from_cents(949)
+        );
+        assert!(with_unavailable.sides.contains_key("queso blanco"));
+    }
+
+    #[test]
+    fn deserialize_item_availability() {
+        // Act
+        let items: Vec<menu::Item> = serde_json::from_value(json!([
+            {"itemCategory": "Entree", "itemType": "Bowl", "itemId": "1", "itemName": "Chicken Bowl", "unitPrice": 8.99, "unitDeliveryPrice": 9.99},
+            {"itemCategory": "Entree", "itemType": "Bowl", "itemId": "2", "itemName": "Steak Bowl", "unitPrice": 9.99, "unitDeliveryPrice": 10.99, "isItemAvailable": false}
+        ])).unwrap();
+
+        // Assert
+        assert!(items[0].is_available);
+        assert!(!items[1].is_available);
+    }
+
+    #[test]
+    fn summarize_calories() {
+        // Arrange
+        let response: menu::Response = serde_json::from_value(json!({
+            "restaurantId": 1234,
+            "entrees": [
+                {"itemCategory": "Entree", "itemType": "Bowl", "itemId": "1", "itemName": "Chicken Bowl", "unitPrice": 8.99, "unitDeliveryPrice": 9.99, "calories": 540},
+                {"itemCategory": "Entree", "itemType": "Bowl", "itemId": "2", "itemName": "Steak Bowl", "unitPrice": 9.99, "unitDeliveryPrice": 10.99}
+            ],
+            "sides": []
+        })).unwrap();
+
+        // Act
+        let menu = Menu::try_from(response).unwrap();
+
+        // Assert
+        assert_eq!(menu.bowl_price("chicken").unwrap().calories, Some(540));
+        assert_eq!(menu.bowl_price("steak").unwrap().calories, None);
+        let serialized = serde_json::to_value(&menu).unwrap();
+        assert_eq!(serialized["bowl_prices"]["chicken"]["calories"], 540);
+        assert!(serialized["bowl_prices"]["steak"].get("calories").is_none());
+    }
+
+    #[test]
+    fn summarize_calorie_ranges() {
+        // Arrange
+        let item = |id: &str, protein: &str, calories: serde_json::Value| {
+            json!({
+                "itemCategory": "Entree",
+                "itemType": "Bowl",
+                "itemId": id,
+                "itemName": format!("{protein} Bowl"),
+                "unitPrice": 8.99,
+                "unitDeliveryPrice": 9.99,
+                "calories": calories
+            })
+        };
+        let response: menu::Response = serde_json::from_value(json!({
+            "restaurantId": 1234,
+            "entrees": [
+                item("1", "Chicken", json!("180-270")),
+                item("2", "Steak", json!({"min": 150, "max": 300})),
+                item("3", "Barbacoa", json!(540.0)),
+                item("4", "Carnitas", json!("varies")),
+                item("5", "Sofritas", json!(null))
+            ],
+            "sides": []
+        }))
+        .unwrap();
+
+        // Act
+        let menu = Menu::try_from(response).unwrap();
+
+        // Assert
+        let calories = |protein: &str| menu.bowl_price(protein).unwrap().calories;
+        assert_eq!(calories("chicken"), Some(180));
+        assert_eq!(calories("steak"), Some(150));
+        assert_eq!(calories("barbacoa"), Some(540));
+        assert_eq!(calories("carnitas"), None);
+        assert_eq!(calories("sofritas"), None);
+    }
+
+    proptest! {
+        #[test]
+        fn parse_arbitrary_body_never_panics(body in ".*") {
+            if let Ok(response) = serde_json::from_str::<menu::Response>(&body) {
+                let _ = Menu::try_from(response);
+            }
+        }
+
+        #[test]
+        fn translate_arbitrary_items_never_panics(
+            entrees in proptest::collection::vec(arbitrary_item(), 0..8),
+            sides in proptest::collection::vec(arbitrary_item(), 0..4),
+        ) {
+            let response = menu::Response { restaurant_id: 1234, entrees, sides };
+            let _ = Menu::try_from(response);
+        }
+    }
+
+    #[test]
+    fn endpoint_from_template_single_token() {
+        // Act
+        let endpoint =
+            Endpoint::from_template("https://example.com/restaurants/$store_id/onlinemenu");
+
+        // Assert
+        assert_eq!(endpoint.unwrap().replace_token, "$store_id");
+    }
+
+    #[test]
+    fn endpoint_from_template_no_token() {
+        // Act
+        let endpoint = Endpoint::from_template("https://example.com/restaurants/onlinemenu");
+
+        // Assert
+        assert!(matches!(
+            endpoint.unwrap_err(),
+            EndpointError::NoReplaceToken
+        ));
+    }
+
+    #[test]
+    fn endpoint_from_template_multiple_tokens() {
+        // Act
+        let endpoint =
+            Endpoint::from_template("https://example.com/$region/restaurants/$store/onlinemenu");
+
+        // Assert
+        assert!(matches!(
+            endpoint.unwrap_err(),
+            EndpointError::MultipleReplaceTokens(_)
+        ));
+    }
+
+    #[test]
+    fn endpoint_try_new_requires_token() {
+        // Act
+        let endpoint = Endpoint::try_new(
+            "https://example.com/restaurants/$store/onlinemenu",
+            "$store",
+        );
+        let mismatched = Endpoint::try_new(
+            "https://example.com/restaurants/$store_id/onlinemenu",
+            "$store",
+        );
+
+        // Assert
+        assert_eq!(endpoint.unwrap().replace_token, "$store");
+        assert!(matches!(
+            mismatched.unwrap_err(),
+            EndpointError::ReplaceTokenNotInEndpoint { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn get_custom_rejects_endpoint_without_token() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        let menu_mock = server
+            .mock_async(|when, then| {
+                when.any_request();
+                then.status(200);
+            })
+            .await;
+        // Copied from a config using the `$store_id` token rather than `$store`
+        let url = server.url("/restaurants/$store_id/onlinemenu");
+        let client = reqwest::Client::new();
+        let api_key = ApiKey::from_raw(FAKE_API_KEY);
+
+        // Act
+        let menu = Menu::get_custom(&LocationId(1234), &api_key, &client, Some(url.as_str())).await;
+
+        // Assert
+        let GetError::BuildError(message) = menu.unwrap_err() else {
+            panic!("expected a build error");
+        };
+        assert!(message.contains("$store"), "unexpected message {message:?}");
+        menu_mock.assert_hits(0);
+    }
+
+    #[test]
+    fn endpoint_to_url_substitutes_id() {
+        // Arrange
+        let endpoint = Endpoint::from_template(DEFAULT_MENU_SERVICE_URL_FORMAT).unwrap();
+
+        // Act
+        let url = endpoint.to_url(&LocationId(1234));
+
+        // Assert
+        assert!(
+            url.is_ok(),
+            "Failed to build menu URL: {:?}",
+            url.unwrap_err()
+        );
+        assert!(url.unwrap().path().contains("/restaurants/1234/"));
+    }
+
+    #[test]
+    fn endpoint_to_url_ignores_token_inside_longer_word() {
+        // Arrange
+        let endpoint = Endpoint {
+            url: "https://example.com/restaurants/$id/menu?ref=$idx&store=$id".to_string(),
+            replace_token: "$id".to_string(),
+        };
+        let bare_endpoint = Endpoint {
+            url: "https://example.com/stores/id/menu?grid=1&id".to_string(),
+            replace_token: "id".to_string(),
+        };
+
+        // Act
+        let url = endpoint.to_url(&LocationId(1234)).unwrap();
+        let bare_url = bare_endpoint.to_url(&LocationId(1234)).unwrap();
+
+        // Assert
+        assert_eq!(
+            url.as_str(),
+            "https://example.com/restaurants/1234/menu?ref=$idx&store=1234"
+        );
+        assert_eq!(
+            bare_url.as_str(),
+            "https://example.com/stores/1234/menu?grid=1&1234"
+        );
+    }
+
+    #[test]
+    fn endpoint_to_url_invalid_after_substitution() {
+        // Arrange
+        let endpoint = Endpoint::from_template("http://[$store]/onlinemenu").unwrap();
+
+        // Act
+        let url = endpoint.to_url(&LocationId(1234));
+
+        // Assert
+        assert!(matches!(url.unwrap_err(), GetError::BuildError(_)));
+    }
+
+    #[tokio::test]
+    async fn get_invalid_template() {
+        // Arrange
+        let client = reqwest::Client::new();
+        let api_key = ApiKey::from_raw(FAKE_API_KEY);
+
+        // Act
+        let menu = Menu::get_custom(
+            &LocationId(1234),
+            &api_key,
+            &client,
+            Some("$store/onlinemenu"),
+        )
+        .await;
+
+        // Assert
+        assert!(matches!(menu.unwrap_err(), GetError::BuildError(_)));
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn get_custom_blocking_success() {
+        // Arrange
+        let server = MockServer::start();
+        let menu_mock = server.mock(|when, then| {
+            when.path("/1234").header(API_KEY_HEADER, FAKE_API_KEY);
+            then.status(200).json_body(json!({
+                "restaurantId": 1234,
+                "entrees": [{"itemCategory": "Entree", "itemType": "Bowl", "itemId": "1", "itemName": "Chicken Bowl", "unitPrice": 8.99, "unitDeliveryPrice": 9.99}],
+                "sides": []
+            }));
+        });
+        let url = server.url("/$store");
+        let client = reqwest::blocking::Client::new();
+        let api_key = ApiKey::from_raw(FAKE_API_KEY);
+
+        // Act
+        let menu =
+            Menu::get_custom_blocking(&LocationId(1234), &api_key, &client, Some(url.as_str()));
+
+        // Assert
+        assert_eq!(
+            menu.unwrap().bowl_price("chicken"),
+            Some(&Price {
+                normal_price: Money::from_cents(899),
+                delivery_price: Money::from_cents(999),
+                calories: None,
+            })
+        );
+        menu_mock.assert();
+    }
+
+    #[tokio::test]
+    async fn get_custom_retries_rate_limits() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        let menu_mock = server
+            .mock_async(|when, then| {
+                when.path("/1234");
+                then.status(429);
+            })
+            .await;
+        let url = server.url("/$store");
+        let client = reqwest::Client::new();
+        let api_key = ApiKey::from_raw(FAKE_API_KEY);
+
+        // Act
+        let menu = Menu::get_custom_with_retries(
+            &LocationId(1234),
+            &api_key,
+            &client,
+            Some(url.as_str()),
+            3,
+            Duration::ZERO,
+        )
+        .await;
+
+        // Assert
+        assert!(matches!(
+            menu.unwrap_err(),
+            GetError::ResponseError {
+                status: StatusCode::TOO_MANY_REQUESTS,
+                ..
+            }
+        ));
+        menu_mock.assert_hits(3);
+    }
+
+    #[tokio::test]
+    async fn get_timeout_is_distinct_error() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        let menu_mock = server
+            .mock_async(|when, then| {
+                when.path("/1234");
+                then.status(200).delay(Duration::from_millis(500));
+            })
+            .await;
+        let url = server.url("/$store");
+        let client = crate::default_http_client_with_timeout(Duration::from_millis(50));
+        let api_key = ApiKey::from_raw(FAKE_API_KEY);
+
+        // Act
+        let menu = Menu::get_custom_with_retries(
+            &LocationId(1234),
+            &api_key,
+            &client,
+            Some(url.as_str()),
+            1,
+            Duration::ZERO,
+        )
+        .await;
+
+        // Assert
+        let error = menu.unwrap_err();
+        assert!(
+            matches!(error, GetError::Timeout(_)),
+            "Expected a timeout, got {error:?}"
+        );
+        assert_eq!(error.kind(), "timeout");
+        menu_mock.assert();
+    }
+
+    #[tokio::test]
+    async fn get_raw_keeps_every_item() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        let item = |item_type: &str, item_name: &str| {
+            json!({
+                "itemCategory": "Entree",
+                "itemType": item_type,
+                "itemId": "1",
+                "itemName": item_name,
+                "unitPrice": 8.99,
+                "unitDeliveryPrice": 9.99
+            })
+        };
+        let menu_mock = server
+            .mock_async(|when, then| {
+                when.path("/1234");
+                then.status(200).json_body(json!({
+                    "restaurantId": 1234,
+                    "entrees": [item("Bowl", "Chicken Bowl"), item("Burrito", "Chicken Burrito"), item("Bowl", "Lifestyle Bowl")],
+                    "sides": [item("Side", "Chips & Guacamole")]
+                }));
+            })
+            .await;
+        let url = server.url("/$store");
+        let client = reqwest::Client::new();
+        let api_key = ApiKey::from_raw(FAKE_API_KEY);
+
+        // Act
+        let response =
+            Menu::get_raw(&LocationId(1234), &api_key, &client, Some(url.as_str())).await;
+
+        // Assert
+        assert!(
+            response.is_ok(),
+            "Failed to get menu: {:?}",
+            response.unwrap_err()
+        );
+        let response = response.unwrap();
+        assert_eq!(response.restaurant_id, 1234);
+        let entrees: Vec<_> = response
+            .entrees
+            .iter()
+            .map(|item| item.item_name.as_str())
+            .collect();
+        assert_eq!(
+            entrees,
+            ["Chicken Bowl", "Chicken Burrito", "Lifestyle Bowl"]
+        );
+        assert_eq!(response.sides.len(), 1);
+        menu_mock.assert();
+    }
+
+    #[tokio::test]
+    async fn get_catering_success() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        let catering_mock = server
+            .mock_async(|when, then| {
+                when.path("/1234").header(API_KEY_HEADER, FAKE_API_KEY);
+                then.status(200).json_body(json!({
+                    "restaurantId": 1234,
+                    "cateringItems": [
+                        {
+                            "itemCategory": "Catering",
+                            "itemType": "Bar",
+                            "itemId": "CAT-1",
+                            "itemName": "Burrito Bowl Bar",
+                            "unitPrice": 13.5,
+                            "minimumQuantity": 10
+                        },
+                        {
+                            "itemCategory": "Catering",
+                            "itemType": "Package",
+                            "itemId": "CAT-2",
+                            "itemName": "Chips & Guacamole Box",
+                            "unitPrice": 39.95,
+                            "serves": 10
+                        }
+                    ]
+                }));
+            })
+            .await;
+        let url = server.url("/$store");
+        let client = reqwest::Client::new();
+        let api_key = ApiKey::from_raw(FAKE_API_KEY);
+
+        // Act
+        let menu =
+            Menu::get_catering(&LocationId(1234), &api_key, &client, Some(url.as_str())).await;
+
+        // Assert
+        assert!(
+            menu.is_ok(),
+            "Failed to get catering menu: {:?}",
+            menu.unwrap_err()
+        );
+        let menu = menu.unwrap();
+        assert_eq!(
+            menu.items["burrito bowl bar"],
+            CateringPrice {
+                price: Money::from_cents(1350),
+                minimum_quantity: Some(10),
+                serves: None
+            }
+        );
+        assert_eq!(
+            menu.items["chips & guacamole box"].price_per_person(),
+            Some(Money::from_cents(400))
+        );
+        catering_mock.assert();
+    }
+
+    #[tokio::test]
+    async fn get_body_over_limit_is_rejected() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        let menu_mock = server
+            .mock_async(|when, then| {
+                when.path("/1234");
+                then.status(200).body("x".repeat(2048));
+            })
+            .await;
+        let endpoint = Endpoint::try_new(&server.url("/$store"), "$store").unwrap();
+        let options = MenuOptionsBuilder::default()
+            .max_body_bytes(1024_usize)
+            .build()
+            .unwrap();
+        let client = reqwest::Client::new();
+        let api_key = ApiKey::from_raw(FAKE_API_KEY);
+
+        // Act
+        let menu =
+            Menu::get_with_options(&LocationId(1234), &api_key, &client, &endpoint, &options).await;
+
+        // Assert
+        let error = menu.unwrap_err();
+        assert!(
+            matches!(error, GetError::BodyTooLarge { limit: 1024 }),
+            "Expected an oversized body, got {error:?}"
+        );
+        assert_eq!(error.kind(), "body_too_large");
+        menu_mock.assert();
+    }
+
+    #[tokio::test]
+    async fn get_html_page_is_unexpected_content_type() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        let menu_mock = server
+            .mock_async(|when, then| {
+                when.path("/1234");
+                then.status(200)
+                    .header("Content-Type", "text/html; charset=utf-8")
+                    .body("<!DOCTYPE html><html><body>Please verify you are a human</body></html>");
+            })
+            .await;
+        let url = server.url("/$store");
+        let client = reqwest::Client::new();
+        let api_key = ApiKey::from_raw(FAKE_API_KEY);
+
+        // Act
+        let menu = Menu::get_custom_with_retries(
+            &LocationId(1234),
+            &api_key,
+            &client,
+            Some(url.as_str()),
+            1,
+            Duration::ZERO,
+        )
+        .await;
+
+        // Assert
+        let error = menu.unwrap_err();
+        assert!(
+            matches!(&error, GetError::UnexpectedContentType { content_type } if content_type == "text/html; charset=utf-8"),
+            "Expected an unexpected content type, got {error:?}"
+        );
+        assert_eq!(error.kind(), "unexpected_content_type");
+        menu_mock.assert();
+    }
+
+    #[test]
+    fn parse_response_body_checks_for_json() {
+        // Arrange
+        let json_body = r#"{"restaurantId": 1234, "entrees": [], "sides": []}"#;
+
+        // Act
+        let untyped_json = parse_response_body::<Response>(None, json_body);
+        let untyped_html = parse_response_body::<Response>(None, "<html></html>");
+        let malformed_json = parse_response_body::<Response>(
+            Some("application/json".to_string()),
+            "{\"restaurantId\":",
+        );
+
+        // Assert
+        assert!(untyped_json.is_ok());
+        assert!(
+            matches!(untyped_html.unwrap_err(), GetError::UnexpectedContentType { content_type } if content_type == "no content type")
+        );
+        assert!(matches!(
+            malformed_json.unwrap_err(),
+            GetError::ParseError(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn get_custom_does_not_retry_forbidden() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        let menu_mock = server
+            .mock_async(|when, then| {
+                when.path("/1234");
+                then.status(403)
+                    .json_body(json!({"message": "invalid subscription key"}));
+            })
+            .await;
+        let url = server.url("/$store");
+        let client = reqwest::Client::new();
+        let api_key = ApiKey::from_raw(FAKE_API_KEY);
+
+        // Act
+        let menu = Menu::get_custom_with_retries(
+            &LocationId(1234),
+            &api_key,
+            &client,
+            Some(url.as_str()),
+            3,
+            Duration::ZERO,
+        )
+        .await;
+
+        // Assert
+        let error = menu.unwrap_err();
+        assert!(error
+            .to_string()
+            .ends_with(r#"403 Forbidden: {"message":"invalid subscription key"}"#));
+        assert!(matches!(
+            error,
+            GetError::ResponseError {
+                status: StatusCode::FORBIDDEN,
+                ..
+            }
+        ));
+        menu_mock.assert_hits(1);
+    }
+
+    #[tokio::test]
+    async fn get_with_pool_alternates_keys() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        let key_a_mock = server
+            .mock_async(|when, then| {
+                when.path("/1234").header(API_KEY_HEADER, "key-a");
+                then.status(404);
+            })
+            .await;
+        let key_b_mock = server
+            .mock_async(|when, then| {
+                when.path("/1234").header(API_KEY_HEADER, "key-b");
+                then.status(404);
+            })
+            .await;
+        let url = server.url("/$store");
+        let client = reqwest::Client::new();
+        let pool = ApiKeyPool::new(vec![ApiKey::from_raw("key-a"), ApiKey::from_raw("key-b")]);
+
+        // Act
+        for _ in 0..4 {
+            let _ =
+                Menu::get_with_pool(&LocationId(1234), &pool, &client, Some(url.as_str())).await;
+        }
+
+        // Assert
+        key_a_mock.assert_hits(2);
+        key_b_mock.assert_hits(2);
+    }
+
+    #[tokio::test]
+    async fn get_with_pool_drops_forbidden_key() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        let forbidden_mock = server
+            .mock_async(|when, then| {
+                when.path("/1234").header(API_KEY_HEADER, "key-a");
+                then.status(403);
+            })
+            .await;
+        let allowed_mock = server
+            .mock_async(|when, then| {
+                when.path("/1234").header(API_KEY_HEADER, "key-b");
+                then.status(404);
+            })
+            .await;
+        let url = server.url("/$store");
+        let client = reqwest::Client::new();
+        let pool = ApiKeyPool::new(vec![ApiKey::from_raw("key-a"), ApiKey::from_raw("key-b")]);
+
+        // Act
+        let menu = Menu::get_with_pool(&LocationId(1234), &pool, &client, Some(url.as_str())).await;
+
+        // Assert
+        assert!(matches!(
+            menu.unwrap_err(),
+            GetError::ResponseError {
+                status: StatusCode::NOT_FOUND,
+                ..
+            }
+        ));
+        assert_eq!(pool.len(), 1);
+        forbidden_mock.assert_hits(1);
+        allowed_mock.assert_hits(1);
+    }
+
+    #[tokio::test]
+    async fn get_with_fallbacks_uses_secondary() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        let response_json = json!({
+            "restaurantId": 1234,
+            "entrees": [
+                {
+                    "itemCategory": "Entree",
+                    "itemType": "Bowl",
+                    "itemId": "1",
+                    "itemName": "Veggie Bowl",
+                    "unitPrice": 7.99,
+                    "unitDeliveryPrice": 8.99
+                },
+                {
+                    "itemCategory": "Entree",
+                    "itemType": "Bowl",
+                    "itemId": "2",
+                    "itemName": "Chicken Bowl",
+                    "unitPrice": 8.99,
+                    "unitDeliveryPrice": 9.99
+                },
+                {
+                    "itemCategory": "Entree",
+                    "itemType": "Bowl",
+                    "itemId": "3",
+                    "itemName": "Steak Bowl",
+                    "unitPrice": 9.99,
+                    "unitDeliveryPrice": 10.99
+                }
+            ],
+            "sides": []
+        });
+        let primary_mock = server
+            .mock_async(|when, then| {
+                when.path("/primary/1234");
+                then.status(503);
+            })
+            .await;
+        let secondary_mock = server
+            .mock_async(|when, then| {
+                when.path("/secondary/1234")
+                    .header(API_KEY_HEADER, FAKE_API_KEY);
+                then.status(200).json_body(response_json);
+            })
+            .await;
+        // The fallback uses a different replace token than the primary endpoint
+        let endpoints = [
+            Endpoint::from_template(&server.url("/primary/$store")).unwrap(),
+            Endpoint::from_template(&server.url("/secondary/$store_id")).unwrap(),
+        ];
+        let client = reqwest::Client::new();
+        let api_key = ApiKey::from_raw(FAKE_API_KEY);
+
+        // Act
+        let menu = Menu::get_with_fallbacks(&LocationId(1234), &api_key, &client, &endpoints).await;
+
+        // Assert
+        assert!(menu.is_ok(), "Failed to get menu: {:?}", menu.unwrap_err());
+        assert_eq!(
+            menu.unwrap().bowl_price("chicken"),
+            Some(&Price {
+                normal_price: Money::from_cents(899),
+                delivery_price: Money::from_cents(999),
+                calories: None,
+            })
+        );
+        primary_mock.assert();
+        secondary_mock.assert();
+    }
+
+    #[tokio::test]
+    async fn get_with_fallbacks_stops_on_client_error() {
+        // Arrange
+        let server = MockServer::start_async().await;
+        let primary_mock = server
+            .mock_async(|when, then| {
+                when.path("/primary/1234");
+                then.status(403);
+            })
+            .await;
+        let secondary_mock = server
+            .mock_async(|when, then| {
+                when.path("/secondary/1234");
+                then.status(200);
+            })
+            .await;
+        let endpoints = [
+            Endpoint::from_template(&server.url("/primary/$store")).unwrap(),
+            Endpoint::from_template(&server.url("/secondary/$store")).unwrap(),
+        ];
+        let client = reqwest::Client::new();
+        let api_key = ApiKey::from_raw(FAKE_API_KEY);
+
+        // Act
+        let menu = Menu::get_with_fallbacks(&LocationId(1234), &api_key, &client, &endpoints).await;
+
+        // Assert
+        assert!(matches!(menu.unwrap_err(), GetError::ResponseError { .. }));
+        primary_mock.assert();
+        secondary_mock.assert_hits(0);
+    }
+
+    fn fake_menu(normal_price: f64) -> Menu {
+        Menu {
+            bowl_prices: BTreeMap::from([(
+                "chicken".to_string(),
+                Price {
+                    normal_price: Money::from_dollars(normal_price),
+                    delivery_price: Money::from_dollars(normal_price + 1.0),
+                    calories: None,
+                },
+            )]),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn menu_save_and_load() {
+        // Arrange
+        let menu = fake_menu(8.99);
+        let temp_file = NamedTempFile::new().unwrap();
+
+        // Act
+        let save_result = menu.save(temp_file.path()).await;
+
+        // Assert
+        assert!(
+            save_result.is_ok(),
+            "Failed to save menu: {:?}",
+            save_result.unwrap_err()
+        );
+        assert_eq!(Menu::load(temp_file.path()).await.unwrap(), menu);
+    }
+
+    #[tokio::test]
+    async fn menus_save_and_load() {
+        // Arrange
+        let location = |id: i32| Location {
+            id: LocationId(id),
+            zip_code: "12345".parse().unwrap(),
+            ..Default::default()
+        };
+        let menus = Menus::from(vec![
+            (location(1234), fake_menu(8.99)),
+            (location(5678), fake_menu(9.49)),
+        ]);
+        let temp_file = NamedTempFile::new().unwrap();
+
+        // Act
+        let save_result = menus.save(temp_file.path()).await;
+
+        // Assert
+        assert!(
+            save_result.is_ok(),
+            "Failed to save menus: {:?}",
+            save_result.unwrap_err()
+        );
+        let saved: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(temp_file.path()).unwrap()).unwrap();
+        assert_eq!(saved[0]["location"]["id"], 1234);
+        assert_eq!(
+            saved[1]["menu"]["bowl_prices"]["chicken"]["normal_price"],
+            9.49
+        );
+        assert_eq!(Menus::load(temp_file.path()).await.unwrap(), menus);
+    }
+
+    #[tokio::test]
+    async fn menus_with_deltas_since_saved_dump() {
+        // Arrange
+        let location = |id: i32| Location {
+            id: LocationId(id),
+            zip_code: "12345".parse().unwrap(),
+            ..Default::default()
+        };
+        let yesterday = Menus::from(vec![(location(1234), fake_menu(8.99))]);
+        let temp_file = NamedTempFile::new().unwrap();
+        yesterday.save(temp_file.path()).await.unwrap();
+        let today = Menus::from(vec![
+            (location(1234), fake_menu(9.49)),
+            (location(5678), fake_menu(9.49)),
+        ]);
+
+        // Act
+        let summaries = today.with_deltas_since(temp_file.path()).await.unwrap();
+
+        // Assert
+        let delta = summaries[0].delta.as_ref().unwrap();
+        assert_eq!(delta.len(), 2);
+        assert_eq!(delta[0].old_price, Some(Money::from_cents(899)));
+        assert_eq!(delta[0].new_price, Some(Money::from_cents(949)));
+        assert_eq!(summaries[1].delta, None);
+    }
+
+    #[tokio::test]
+    async fn menus_load_errors() {
+        // Arrange
+        let mut temp_file = NamedTempFile::new().unwrap();
+        write!(temp_file, "not json").unwrap();
+
+        // Act
+        let missing = Menus::load("totally_nonexistent_dir/menus.json").await;
+        let bad_json = Menus::load(temp_file.path()).await;
+
+        // Assert
+        assert!(matches!(missing.unwrap_err(), LoadError::ReadError { .. }));
+        let bad_json = bad_json.unwrap_err();
+        assert!(bad_json
+            .to_string()
+            .contains(&temp_file.path().display().to_string()));
+        assert!(matches!(bad_json, LoadError::ParseError { .. }));
+    }
+
+    #[cfg(feature = "schema")]
+    #[test]
+    fn menu_record_schema_describes_output() {
+        // Act
+        let schema = menu_record_schema();
+
+        // Assert
+        assert_eq!(schema["required"], json!(["location", "menu"]));
+        assert_eq!(schema["$defs"]["Money"]["type"], "number");
+        assert_eq!(schema["$defs"]["LocationId"]["type"], "integer");
+    }
+}
@@ -0,0 +1,124 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::Serialize;
+
+use crate::{
+    menu::{Item, Response},
+    LocationId,
+};
+
+/// An item seen on at least one store's raw menu.
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct CatalogItem {
+    /// The item's name as the first store offering it spells it.
+    pub item_name: String,
+    pub item_category: String,
+    pub item_type: String,
+    /// Every store whose menu lists the item.
+    pub stores: BTreeSet<LocationId>,
+}
+
+/// Collect every distinct entree and side across stores' raw menus, keyed by item ID, or by normalized name
+/// for items without one. An item offered by only some of the stores is a regional item.
+pub fn item_catalog(responses: &[(LocationId, Response)]) -> BTreeMap<String, CatalogItem> {
+    let mut catalog: BTreeMap<String, CatalogItem> = BTreeMap::new();
+    for (store, response) in responses {
+        for item in response.entrees.iter().chain(&response.sides) {
+            catalog
+                .entry(catalog_key(item))
+                .or_insert_with(|| CatalogItem {
+                    item_name: item.item_name.clone(),
+                    item_category: item.item_category.clone(),
+                    item_type: item.item_type.clone(),
+                    stores: BTreeSet::new(),
+                })
+                .stores
+                .insert(*store);
+        }
+    }
+    catalog
+}
+
+/// The item's ID, or its lowercased name with whitespace collapsed if the ID is blank.
+fn catalog_key(item: &Item) -> String {
+    match item.item_id.trim() {
+        "" => item
+            .item_name
+            .to_lowercase()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" "),
+        id => id.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(item_id: &str, item_name: &str) -> Item {
+        serde_json::from_value(serde_json::json!({
+            "itemCategory": "Entree",
+            "itemType": "Bowl",
+            "itemId": item_id,
+            "itemName": item_name,
+            "unitPrice": 8.99,
+            "unitDeliveryPrice": 9.99
+        }))
+        .unwrap()
+    }
+
+    fn response(store: i32, entrees: Vec<Item>) -> (LocationId, Response) {
+        let response = Response {
+            restaurant_id: store,
+            entrees,
+            sides: vec![],
+        };
+        (LocationId(store), response)
+    }
+
+    #[test]
+    fn item_catalog_finds_regional_items() {
+        // Arrange
+        let responses = [
+            response(1, vec![item("CMG-1", "Chicken Bowl")]),
+            response(
+                2,
+                vec![
+                    item("CMG-1", "Chicken Bowl"),
+                    item("CMG-9", "Carne Asada Bowl"),
+                ],
+            ),
+            response(3, vec![item("CMG-1", "Chicken Bowl")]),
+        ];
+
+        // Act
+        let catalog = item_catalog(&responses);
+
+        // Assert
+        assert_eq!(catalog.len(), 2);
+        let all_stores: BTreeSet<_> = [1, 2, 3].into_iter().map(LocationId).collect();
+        assert_eq!(catalog["CMG-1"].stores, all_stores);
+        assert_eq!(catalog["CMG-9"].item_name, "Carne Asada Bowl");
+        assert_eq!(catalog["CMG-9"].stores, BTreeSet::from([LocationId(2)]));
+    }
+
+    #[test]
+    fn item_catalog_keys_items_without_ids_by_name() {
+        // Arrange
+        let responses = [
+            response(1, vec![item("", "Chicken  Bowl")]),
+            response(2, vec![item(" ", "chicken bowl")]),
+        ];
+
+        // Act
+        let catalog = item_catalog(&responses);
+
+        // Assert
+        assert_eq!(catalog.len(), 1);
+        assert_eq!(catalog["chicken bowl"].item_name, "Chicken  Bowl");
+        assert_eq!(catalog["chicken bowl"].stores.len(), 2);
+        let json = serde_json::to_value(&catalog).unwrap();
+        assert_eq!(json["chicken bowl"]["stores"], serde_json::json!([1, 2]));
+    }
+}
@@ -0,0 +1,128 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{api_interfaces::catering, menu::Money};
+
+/// Catering prices for one store, keyed by lowercased item name (e.g. `burrito bowl bar`).
+/// Only items the store currently sells are included.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CateringMenu {
+    pub items: BTreeMap<String, CateringPrice>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CateringPrice {
+    /// Price of one unit: per person for build-your-own bars, per package otherwise.
+    pub price: Money,
+    /// Fewest units that can be ordered, if there's a minimum.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub minimum_quantity: Option<u32>,
+    /// People one unit serves, if the item is sold by the package.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub serves: Option<u32>,
+}
+
+impl CateringPrice {
+    /// The price per person, for packages that say how many they serve. Rounded to the nearest cent.
+    pub fn price_per_person(&self) -> Option<Money> {
+        self.serves
+            .filter(|&serves| serves > 0)
+            .map(|serves| Money::from_dollars(self.price.as_dollars() / f64::from(serves)))
+    }
+}
+
+impl From<catering::Response> for CateringMenu {
+    /// Items with a negative or non-finite price are skipped, like sides on the regular menu.
+    fn from(response: catering::Response) -> Self {
+        let items = response
+            .catering_items
+            .into_iter()
+            .filter(|item| item.is_available)
+            .filter_map(|item| {
+                let name = item.item_name.to_lowercase().split_whitespace().collect::<Vec<_>>().join(" ");
+                if !item.unit_price.is_finite() || item.unit_price < 0.0 {
+                    tracing::debug!(item = %name, price = item.unit_price, "skipping catering item with an invalid price");
+                    return None;
+                }
+                let price = CateringPrice {
+                    price: Money::from_dollars(item.unit_price.into()),
+                    minimum_quantity: item.minimum_quantity,
+                    serves: item.serves,
+                };
+                Some((name, price))
+            })
+            .collect();
+        Self { items }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(
+        item_name: &str,
+        unit_price: f32,
+        serves: Option<u32>,
+        is_available: bool,
+    ) -> catering::Item {
+        catering::Item {
+            item_category: "Catering".to_string(),
+            item_type: "Package".to_string(),
+            item_id: "1".to_string(),
+            item_name: item_name.to_string(),
+            unit_price,
+            minimum_quantity: None,
+            serves,
+            is_available,
+        }
+    }
+
+    #[test]
+    fn from_response_skips_unavailable_and_invalid_items() {
+        // Arrange
+        let response = catering::Response {
+            restaurant_id: 1234,
+            catering_items: vec![
+                item("Chips &  Salsa Box", 25.0, Some(10), true),
+                item("Queso Blanco Box", 30.0, Some(10), false),
+                item("Guacamole Box", -1.0, Some(10), true),
+            ],
+        };
+
+        // Act
+        let menu = CateringMenu::from(response);
+
+        // Assert
+        assert_eq!(menu.items.len(), 1);
+        assert_eq!(
+            menu.items["chips & salsa box"].price,
+            Money::from_cents(2500)
+        );
+    }
+
+    #[test]
+    fn price_per_person_needs_serving_count() {
+        // Arrange
+        let package = CateringPrice {
+            price: Money::from_cents(2999),
+            minimum_quantity: None,
+            serves: Some(4),
+        };
+        let per_person = CateringPrice {
+            serves: None,
+            ..package.clone()
+        };
+        let serves_nobody = CateringPrice {
+            serves: Some(0),
+            ..package.clone()
+        };
+
+        // Act
+        let prices = [package, per_person, serves_nobody].map(|price| price.price_per_person());
+
+        // Assert
+        assert_eq!(prices, [Some(Money::from_cents(750)), None, None]);
+    }
+}
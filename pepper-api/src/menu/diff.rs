@@ -0,0 +1,194 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::{
+    locations::Location,
+    menu::{Menu, Money, Price},
+    LocationId,
+};
+
+/// A price that differs between two menu dumps for one item, channel and store.
+/// A missing price means the store or item only appears in one of the dumps.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct PriceChange {
+    pub restaurant_id: LocationId,
+    pub item: String,
+    pub channel: &'static str,
+    pub old_price: Option<Money>,
+    pub new_price: Option<Money>,
+}
+
+/// Compare two menu dumps store by store, returning one change per differing price.
+/// Changes are ordered by restaurant ID, then item, then channel.
+pub fn diff_menus(old: &[(Location, Menu)], new: &[(Location, Menu)]) -> Vec<PriceChange> {
+    let old = menus_by_id(old);
+    let new = menus_by_id(new);
+    let mut ids: Vec<LocationId> = old.keys().chain(new.keys()).copied().collect();
+    ids.sort_unstable();
+    ids.dedup();
+
+    ids.into_iter()
+        .flat_map(|id| diff_store(id, old.get(&id).copied(), new.get(&id).copied()))
+        .collect()
+}
+
+/// A store's menu as freshly fetched, with how its prices moved since an earlier dump.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct SummaryWithDelta {
+    pub location: Location,
+    pub menu: Menu,
+    /// The store's changes since the earlier dump, ordered like [`diff_menus`]; `None` if it wasn't in that dump.
+    pub delta: Option<Vec<PriceChange>>,
+}
+
+/// Annotate each store in `current` with its price changes since `previous`, e.g. yesterday's dump.
+/// Stores only in `previous` are left out.
+pub fn with_deltas(
+    previous: &[(Location, Menu)],
+    current: Vec<(Location, Menu)>,
+) -> Vec<SummaryWithDelta> {
+    let previous = menus_by_id(previous);
+    current
+        .into_iter()
+        .map(|(location, menu)| {
+            let delta = previous
+                .get(&location.id)
+                .map(|old| diff_store(location.id, Some(old), Some(&menu)));
+            SummaryWithDelta {
+                location,
+                menu,
+                delta,
+            }
+        })
+        .collect()
+}
+
+/// One store's differing prices, ordered by item, then channel. A missing menu has no prices.
+fn diff_store(id: LocationId, old: Option<&Menu>, new: Option<&Menu>) -> Vec<PriceChange> {
+    let old_prices = old.map(prices_by_item).unwrap_or_default();
+    let new_prices = new.map(prices_by_item).unwrap_or_default();
+    let mut items: Vec<&String> = old_prices.keys().chain(new_prices.keys()).collect();
+    items.sort_unstable();
+    items.dedup();
+
+    let mut changes = Vec::new();
+    for item in items {
+        let old_price = old_prices.get(item);
+        let new_price = new_prices.get(item);
+        for (channel, old_price, new_price) in [
+            (
+                "pickup",
+                old_price.map(|price| price.normal_price),
+                new_price.map(|price| price.normal_price),
+            ),
+            (
+                "delivery",
+                old_price.map(|price| price.delivery_price),
+                new_price.map(|price| price.delivery_price),
+            ),
+        ] {
+            if old_price != new_price {
+                changes.push(PriceChange {
+                    restaurant_id: id,
+                    item: item.clone(),
+                    channel,
+                    old_price,
+                    new_price,
+                });
+            }
+        }
+    }
+    changes
+}
+
+fn menus_by_id(records: &[(Location, Menu)]) -> BTreeMap<LocationId, &Menu> {
+    records
+        .iter()
+        .map(|(location, menu)| (location.id, menu))
+        .collect()
+}
+
+fn prices_by_item(menu: &Menu) -> BTreeMap<String, &Price> {
+    menu.prices().into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::menu::test_records::record;
+
+    #[test]
+    fn diff_menus_price_change() {
+        // Arrange
+        let old = [record(1, 8.99), record(2, 8.99)];
+        let new = [record(2, 8.99), record(1, 9.49)];
+
+        // Act
+        let changes = diff_menus(&old, &new);
+
+        // Assert
+        assert_eq!(changes.len(), 2);
+        assert_eq!(
+            changes[0],
+            PriceChange {
+                restaurant_id: LocationId(1),
+                item: "chicken_bowl".to_string(),
+                channel: "pickup",
+                old_price: Some(Money::from_cents(899)),
+                new_price: Some(Money::from_cents(949)),
+            }
+        );
+        assert_eq!(changes[1].channel, "delivery");
+    }
+
+    #[test]
+    fn diff_menus_store_in_one_input() {
+        // Arrange
+        let old = [record(1, 8.99)];
+        let new = [record(1, 8.99), record(2, 8.99)];
+
+        // Act
+        let changes = diff_menus(&old, &new);
+
+        // Assert
+        assert_eq!(changes.len(), 4);
+        assert!(changes
+            .iter()
+            .all(|change| change.restaurant_id == LocationId(2) && change.old_price.is_none()));
+    }
+
+    #[test]
+    fn with_deltas_day_over_day() {
+        // Arrange
+        let yesterday = [record(1, 8.99), record(2, 8.99)];
+        let today = vec![record(1, 9.49), record(2, 8.99), record(3, 8.99)];
+
+        // Act
+        let summaries = with_deltas(&yesterday, today);
+
+        // Assert
+        let deltas: Vec<_> = summaries
+            .iter()
+            .map(|summary| (summary.location.id.0, summary.delta.as_ref().map(Vec::len)))
+            .collect();
+        assert_eq!(deltas, [(1, Some(2)), (2, Some(0)), (3, None)]);
+        let raised = &summaries[0].delta.as_ref().unwrap()[0];
+        assert_eq!(raised.item, "chicken_bowl");
+        assert_eq!(raised.old_price, Some(Money::from_cents(899)));
+        assert_eq!(raised.new_price, Some(Money::from_cents(949)));
+        assert_eq!(summaries[0].menu, record(1, 9.49).1);
+    }
+
+    #[test]
+    fn diff_menus_unchanged() {
+        // Arrange
+        let records = [record(1, 8.99)];
+
+        // Act
+        let changes = diff_menus(&records, &records);
+
+        // Assert
+        assert!(changes.is_empty());
+    }
+}
@@ -0,0 +1,134 @@
+use std::fmt;
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// An amount of money in whole cents, so prices compare exactly and serialize without float noise.
+/// Serializes as a dollar amount (e.g. `7.99`) and deserializes from a number or a string like `"$7.99"`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Money {
+    cents: i64,
+}
+
+impl Money {
+    pub const fn from_cents(cents: i64) -> Self {
+        Self { cents }
+    }
+
+    /// Convert a dollar amount, rounding to the nearest cent.
+    pub fn from_dollars(dollars: f64) -> Self {
+        Self {
+            cents: (dollars * 100.0).round() as i64,
+        }
+    }
+
+    pub const fn cents(self) -> i64 {
+        self.cents
+    }
+
+    pub fn as_dollars(self) -> f64 {
+        self.cents as f64 / 100.0
+    }
+}
+
+/// Renders as `$7.99`, or `-$7.99` for negative amounts.
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.cents < 0 { "-" } else { "" };
+        let cents = self.cents.unsigned_abs();
+        write!(f, "{sign}${}.{:02}", cents / 100, cents % 100)
+    }
+}
+
+/// A dollar amount, matching how [`Money`] serializes.
+#[cfg(feature = "schema")]
+impl schemars::JsonSchema for Money {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "Money".into()
+    }
+
+    fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "type": "number",
+            "description": "A dollar amount, e.g. 7.99"
+        })
+    }
+}
+
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.as_dollars())
+    }
+}
+
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(MoneyVisitor)
+    }
+}
+
+struct MoneyVisitor;
+
+impl de::Visitor<'_> for MoneyVisitor {
+    type Value = Money;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a dollar amount as a number or a string")
+    }
+
+    fn visit_f64<E: de::Error>(self, dollars: f64) -> Result<Money, E> {
+        Ok(Money::from_dollars(dollars))
+    }
+
+    fn visit_i64<E: de::Error>(self, dollars: i64) -> Result<Money, E> {
+        Ok(Money::from_cents(dollars * 100))
+    }
+
+    fn visit_u64<E: de::Error>(self, dollars: u64) -> Result<Money, E> {
+        Ok(Money::from_cents(dollars as i64 * 100))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Money, E> {
+        let dollars = value.trim().trim_start_matches('$');
+        dollars
+            .parse()
+            .map(Money::from_dollars)
+            .map_err(|_| E::invalid_value(de::Unexpected::Str(value), &self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn from_f32_rounds_to_nearest_cent() {
+        // Act
+        let money = Money::from_dollars(7.99f32 as f64);
+
+        // Assert
+        assert_eq!(money, Money::from_cents(799));
+        assert_eq!(serde_json::to_string(&money).unwrap(), "7.99");
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(Money::from_cents(799).to_string(), "$7.99");
+        assert_eq!(Money::from_cents(5).to_string(), "$0.05");
+        assert_eq!(Money::from_cents(-150).to_string(), "-$1.50");
+    }
+
+    #[test]
+    fn deserialize_number_or_string() {
+        // Act
+        let parsed: Vec<Money> =
+            serde_json::from_value(json!([7.99, 8, "9.49", "$10.00"])).unwrap();
+
+        // Assert
+        assert_eq!(
+            parsed,
+            [799, 800, 949, 1000].map(Money::from_cents).to_vec()
+        );
+        assert!(serde_json::from_value::<Money>(json!("cheap")).is_err());
+    }
+}
@@ -0,0 +1,256 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use serde::Serialize;
+
+use crate::{
+    locations::Location,
+    menu::{Menu, Money, Protein},
+    LocationId, ZipCode,
+};
+
+/// How much the normal price of an item varies between stores.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct PriceSpread {
+    pub min: Money,
+    pub max: Money,
+    /// The middle price, or the mean of the two middle prices rounded to the nearest cent.
+    pub median: Money,
+    /// Rounded to the nearest cent.
+    pub mean: Money,
+    /// Difference between the priciest and cheapest store, as a percentage of the cheapest.
+    pub spread_pct: f32,
+    pub cheapest_store: LocationId,
+    pub priciest_store: LocationId,
+}
+
+/// Compute the price spread of every item across all stores, keyed by item name.
+/// Ties between stores with the same price go to the lowest store ID.
+pub fn price_spreads(records: &[(Location, Menu)]) -> BTreeMap<String, PriceSpread> {
+    let mut prices: BTreeMap<String, Vec<(Money, LocationId)>> = BTreeMap::new();
+    for (location, menu) in records {
+        for (item, price) in menu.prices() {
+            prices
+                .entry(item)
+                .or_default()
+                .push((price.normal_price, location.id));
+        }
+    }
+    prices
+        .into_iter()
+        .map(|(item, mut prices)| (item, PriceSpread::new(&mut prices)))
+        .collect()
+}
+
+/// Compute the spread of one bowl's normal price across every store that sells it, e.g. for a national summary.
+/// A store that appears more than once only counts the first time. `None` if no store sells the bowl.
+pub fn national_stats(records: &[(Location, Menu)], protein: Protein) -> Option<PriceSpread> {
+    let mut seen = HashSet::new();
+    let mut prices: Vec<(Money, LocationId)> = records
+        .iter()
+        .filter(|(location, _)| seen.insert(location.id))
+        .filter_map(|(location, menu)| {
+            let price = menu.bowl_price(protein.as_str())?;
+            Some((price.normal_price, location.id))
+        })
+        .collect();
+    (!prices.is_empty()).then(|| PriceSpread::new(&mut prices))
+}
+
+impl PriceSpread {
+    /// `prices` must not be empty. Sorts them by price, then store ID, so ties go to the lowest ID.
+    fn new(prices: &mut [(Money, LocationId)]) -> Self {
+        prices.sort_unstable();
+        let (min, cheapest_store) = prices[0];
+        let max = prices[prices.len() - 1].0;
+        let (_, priciest_store) = prices[prices.partition_point(|(price, _)| *price < max)];
+        let cents = |index: usize| prices[index].0.cents();
+        let middle = prices.len() / 2;
+        let median = if prices.len() % 2 == 1 {
+            Money::from_cents(cents(middle))
+        } else {
+            Money::from_cents(((cents(middle - 1) + cents(middle)) as f64 / 2.0).round() as i64)
+        };
+        let total: i64 = prices.iter().map(|(price, _)| price.cents()).sum();
+        let spread_pct = if min.cents() > 0 {
+            (max.cents() - min.cents()) as f32 / min.cents() as f32 * 100.0
+        } else {
+            0.0
+        };
+        Self {
+            min,
+            max,
+            median,
+            mean: Money::from_cents((total as f64 / prices.len() as f64).round() as i64),
+            spread_pct,
+            cheapest_store,
+            priciest_store,
+        }
+    }
+}
+
+/// Normal bowl prices across the stores in one zip code, keyed by protein.
+#[derive(Debug, Default, Serialize, PartialEq)]
+pub struct ZipPriceStats {
+    pub bowls: BTreeMap<String, BowlPriceStats>,
+}
+
+/// The spread of one bowl's normal price across stores.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct BowlPriceStats {
+    pub min: Money,
+    pub max: Money,
+    /// Rounded to the nearest cent.
+    pub mean: Money,
+    /// Stores the bowl is priced at.
+    pub stores: usize,
+}
+
+/// Compute bowl price statistics for every zip code with at least one store.
+/// A store that appears more than once, e.g. in a resumed crawl, only counts the first time.
+pub fn aggregate_by_zip(records: &[(Location, Menu)]) -> HashMap<ZipCode, ZipPriceStats> {
+    let mut seen = HashSet::new();
+    let mut prices: HashMap<&ZipCode, BTreeMap<&str, Vec<Money>>> = HashMap::new();
+    for (location, menu) in records {
+        if !seen.insert(location.id) {
+            continue;
+        }
+        let zip_prices = prices.entry(&location.zip_code).or_default();
+        for (protein, price) in &menu.bowl_prices {
+            zip_prices
+                .entry(protein)
+                .or_default()
+                .push(price.normal_price);
+        }
+    }
+    prices
+        .into_iter()
+        .map(|(zip_code, bowls)| {
+            let bowls = bowls
+                .into_iter()
+                .map(|(protein, prices)| (protein.to_string(), BowlPriceStats::new(&prices)))
+                .collect();
+            (zip_code.clone(), ZipPriceStats { bowls })
+        })
+        .collect()
+}
+
+impl BowlPriceStats {
+    /// `prices` must not be empty.
+    fn new(prices: &[Money]) -> Self {
+        let total: i64 = prices.iter().map(|price| price.cents()).sum();
+        let mean = (total as f64 / prices.len() as f64).round() as i64;
+        Self {
+            min: *prices.iter().min().expect("at least one price"),
+            max: *prices.iter().max().expect("at least one price"),
+            mean: Money::from_cents(mean),
+            stores: prices.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::menu::test_records::record;
+
+    #[test]
+    fn price_spreads_chicken_bowl() {
+        // Arrange
+        let records = [record(3, 9.0), record(1, 10.0), record(2, 8.0)];
+
+        // Act
+        let spreads = price_spreads(&records);
+
+        // Assert
+        let chicken = &spreads["chicken_bowl"];
+        assert_eq!(chicken.min, Money::from_cents(800));
+        assert_eq!(chicken.max, Money::from_cents(1000));
+        assert!((chicken.spread_pct - 25.0).abs() < 0.001);
+        assert_eq!(chicken.median, Money::from_cents(900));
+        assert_eq!(chicken.mean, Money::from_cents(900));
+        assert_eq!(chicken.cheapest_store, LocationId(2));
+        assert_eq!(chicken.priciest_store, LocationId(1));
+    }
+
+    #[test]
+    fn price_spreads_ties_go_to_lowest_id() {
+        // Arrange
+        let records = [record(3, 8.0), record(1, 8.0), record(2, 8.0)];
+
+        // Act
+        let spreads = price_spreads(&records);
+
+        // Assert
+        let veggie = &spreads["veggie_bowl"];
+        assert_eq!(veggie.spread_pct, 0.0);
+        assert_eq!(veggie.cheapest_store, LocationId(1));
+        assert_eq!(veggie.priciest_store, LocationId(1));
+    }
+
+    #[test]
+    fn aggregate_by_zip_means_per_zip() {
+        // Arrange
+        let in_zip = |(mut location, menu): (Location, Menu), zip_code: &str| {
+            location.zip_code = zip_code.parse().unwrap();
+            (location, menu)
+        };
+        let records = [
+            record(1, 8.0),
+            record(2, 9.0),
+            // A duplicate of store 2 from a resumed crawl
+            record(2, 20.0),
+            in_zip(record(3, 12.0), "54321"),
+        ];
+
+        // Act
+        let stats = aggregate_by_zip(&records);
+
+        // Assert
+        assert_eq!(stats.len(), 2);
+        let chicken = &stats["12345"].bowls["chicken"];
+        assert_eq!(chicken.mean, Money::from_cents(850));
+        assert_eq!(chicken.min, Money::from_cents(800));
+        assert_eq!(chicken.max, Money::from_cents(900));
+        assert_eq!(chicken.stores, 2);
+        assert_eq!(
+            stats["54321"].bowls["chicken"].mean,
+            Money::from_cents(1200)
+        );
+    }
+
+    #[test]
+    fn national_stats_chicken_bowl() {
+        // Arrange
+        let records = [
+            record(3, 9.0),
+            record(1, 10.0),
+            record(4, 8.5),
+            record(2, 8.0),
+            // A duplicate of store 1 from a resumed crawl
+            record(1, 30.0),
+        ];
+
+        // Act
+        let chicken = national_stats(&records, Protein::Chicken);
+        let barbacoa = national_stats(&records, Protein::Barbacoa);
+
+        // Assert
+        let chicken = chicken.unwrap();
+        assert_eq!(chicken.min, Money::from_cents(800));
+        assert_eq!(chicken.max, Money::from_cents(1000));
+        assert_eq!(chicken.median, Money::from_cents(875));
+        assert_eq!(chicken.mean, Money::from_cents(888));
+        assert_eq!(chicken.cheapest_store, LocationId(2));
+        assert_eq!(chicken.priciest_store, LocationId(1));
+        assert_eq!(barbacoa, None);
+    }
+
+    #[test]
+    fn price_spreads_empty() {
+        // Act
+        let spreads = price_spreads(&[]);
+
+        // Assert
+        assert!(spreads.is_empty());
+    }
+}
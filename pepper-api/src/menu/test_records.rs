@@ -0,0 +1,33 @@
+//! Menu records shared by the tests of the modules that compare menus across stores.
+
+use std::collections::BTreeMap;
+
+use crate::{
+    locations::Location,
+    menu::{Menu, Money, Price},
+    LocationId,
+};
+
+/// A store in zip code 12345 selling a $7.99 veggie bowl and a chicken bowl at `chicken_price`,
+/// with delivery a dollar more.
+pub(super) fn record(id: i32, chicken_price: f64) -> (Location, Menu) {
+    let price = |normal_price: f64| Price {
+        normal_price: Money::from_dollars(normal_price),
+        delivery_price: Money::from_dollars(normal_price + 1.0),
+        calories: None,
+    };
+    (
+        Location {
+            id: LocationId(id),
+            zip_code: "12345".parse().unwrap(),
+            ..Default::default()
+        },
+        Menu {
+            bowl_prices: BTreeMap::from([
+                ("veggie".to_string(), price(7.99)),
+                ("chicken".to_string(), price(chicken_price)),
+            ]),
+            ..Default::default()
+        },
+    )
+}
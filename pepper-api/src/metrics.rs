@@ -0,0 +1,75 @@
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+use reqwest::StatusCode;
+
+/// Counters for the HTTP requests made through a [`crate::Client`], shared by all of its clones.
+#[derive(Debug, Default)]
+pub(crate) struct Metrics {
+    requests: AtomicU64,
+    failures: AtomicU64,
+    retries: AtomicU64,
+    bytes_downloaded: AtomicU64,
+    statuses: Mutex<BTreeMap<u16, u64>>,
+}
+
+impl Metrics {
+    /// Record a request that got a response, failed or not.
+    pub(crate) fn record_response(&self, status: StatusCode) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        if !status.is_success() {
+            self.failures.fetch_add(1, Ordering::Relaxed);
+        }
+        *self
+            .statuses
+            .lock()
+            .unwrap()
+            .entry(status.as_u16())
+            .or_default() += 1;
+    }
+
+    /// Record a request that failed without a response, e.g. a connection error or timeout.
+    pub(crate) fn record_request_error(&self) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        self.failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_retry(&self) {
+        self.retries.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_bytes(&self, bytes: usize) {
+        self.bytes_downloaded
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            requests: self.requests.load(Ordering::Relaxed),
+            failures: self.failures.load(Ordering::Relaxed),
+            retries: self.retries.load(Ordering::Relaxed),
+            bytes_downloaded: self.bytes_downloaded.load(Ordering::Relaxed),
+            statuses: self.statuses.lock().unwrap().clone(),
+        }
+    }
+}
+
+/// The request counters of a [`crate::Client`] at one point in time.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MetricsSnapshot {
+    /// HTTP requests sent, counting each retry and each page of locations.
+    pub requests: u64,
+    /// Requests that failed, either without a response or with a non-2xx status.
+    pub failures: u64,
+    /// Requests that were retries of an earlier failed attempt.
+    pub retries: u64,
+    /// Bytes of successful response bodies, after decompression.
+    pub bytes_downloaded: u64,
+    /// Responses received, keyed by status code.
+    pub statuses: BTreeMap<u16, u64>,
+}
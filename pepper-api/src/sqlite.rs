@@ -0,0 +1,178 @@
+use std::path::Path;
+
+use rusqlite::{params, Connection};
+
+use crate::{error::SaveError, locations::Location, menu::Menu};
+
+const CREATE_TABLES: &str = "
+    CREATE TABLE IF NOT EXISTS locations (
+        id INTEGER PRIMARY KEY,
+        zip_code TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS prices (
+        location_id INTEGER NOT NULL REFERENCES locations(id),
+        item TEXT NOT NULL,
+        normal_price_cents INTEGER NOT NULL,
+        delivery_price_cents INTEGER NOT NULL,
+        PRIMARY KEY (location_id, item)
+    );
+";
+
+/// Writes menu summaries into a SQLite database for ad-hoc querying.
+pub struct SqliteWriter {
+    conn: Connection,
+}
+
+impl SqliteWriter {
+    /// Open (or create) the database at the given path, creating the tables if needed.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, SaveError> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(CREATE_TABLES)?;
+        Ok(Self { conn })
+    }
+
+    /// Insert a batch of menus in a single transaction.
+    /// A store's previous prices are replaced, so items it no longer sells don't linger.
+    pub fn write_batch<'a, I>(&mut self, records: I) -> Result<(), SaveError>
+    where
+        I: IntoIterator<Item = (&'a Location, &'a Menu)>,
+    {
+        let tx = self.conn.transaction()?;
+        {
+            let mut insert_location = tx.prepare_cached(
+                "INSERT OR REPLACE INTO locations (id, zip_code) VALUES (?1, ?2)",
+            )?;
+            let mut delete_prices =
+                tx.prepare_cached("DELETE FROM prices WHERE location_id = ?1")?;
+            let mut insert_price = tx.prepare_cached(
+                "INSERT INTO prices (location_id, item, normal_price_cents, delivery_price_cents) VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for (location, menu) in records {
+                insert_location.execute(params![location.id.0, location.zip_code.as_str()])?;
+                delete_prices.execute(params![location.id.0])?;
+                for (item, price) in menu.prices() {
+                    insert_price.execute(params![
+                        location.id.0,
+                        item,
+                        price.normal_price.cents(),
+                        price.delivery_price.cents()
+                    ])?;
+                }
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        menu::{Money, Price},
+        LocationId,
+    };
+    use std::collections::BTreeMap;
+    use tempfile::NamedTempFile;
+
+    fn fake_menu(base_price: f64) -> Menu {
+        let price = Price {
+            normal_price: Money::from_dollars(base_price),
+            delivery_price: Money::from_dollars(base_price + 1.0),
+            calories: None,
+        };
+        Menu {
+            bowl_prices: BTreeMap::from([
+                ("veggie".to_string(), price.clone()),
+                ("chicken".to_string(), price.clone()),
+                ("steak".to_string(), price),
+            ]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn write_batch_success() {
+        // Arrange
+        let temp_file = NamedTempFile::new().unwrap();
+        let mut writer = SqliteWriter::open(temp_file.path()).unwrap();
+        let locations = [
+            Location {
+                id: LocationId(1234),
+                zip_code: "12345".parse().unwrap(),
+                ..Default::default()
+            },
+            Location {
+                id: LocationId(5678),
+                zip_code: "56789".parse().unwrap(),
+                ..Default::default()
+            },
+        ];
+        let menus = [fake_menu(8.99), fake_menu(9.99)];
+
+        // Act
+        let result = writer.write_batch(locations.iter().zip(menus.iter()));
+
+        // Assert
+        assert!(
+            result.is_ok(),
+            "Failed to write batch: {:?}",
+            result.unwrap_err()
+        );
+        let location_count: i64 = writer
+            .conn
+            .query_row("SELECT COUNT(*) FROM locations", [], |row| row.get(0))
+            .unwrap();
+        let price_count: i64 = writer
+            .conn
+            .query_row("SELECT COUNT(*) FROM prices", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(location_count, 2);
+        assert_eq!(price_count, 6);
+        let (normal_cents, delivery_cents): (i64, i64) = writer
+            .conn
+            .query_row(
+                "SELECT normal_price_cents, delivery_price_cents FROM prices WHERE location_id = 1234 AND item = 'chicken_bowl'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((normal_cents, delivery_cents), (899, 999));
+    }
+
+    #[test]
+    fn write_batch_replaces_previous_prices() {
+        // Arrange
+        let temp_file = NamedTempFile::new().unwrap();
+        let mut writer = SqliteWriter::open(temp_file.path()).unwrap();
+        let location = Location {
+            id: LocationId(1234),
+            zip_code: "12345".parse().unwrap(),
+            ..Default::default()
+        };
+        let mut without_steak = fake_menu(9.49);
+        without_steak.bowl_prices.remove("steak");
+        writer.write_batch([(&location, &fake_menu(8.99))]).unwrap();
+
+        // Act
+        writer.write_batch([(&location, &without_steak)]).unwrap();
+
+        // Assert
+        let mut statement = writer
+            .conn
+            .prepare("SELECT item, normal_price_cents FROM prices ORDER BY item")
+            .unwrap();
+        let prices: Vec<(String, i64)> = statement
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            prices,
+            [
+                ("chicken_bowl".to_string(), 949),
+                ("veggie_bowl".to_string(), 949)
+            ]
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use std::{borrow::Borrow, fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::ZipCodeError;
+
+/// A location's zip code. Serializes as the bare string.
+/// Parsing accepts a 5-digit US zip or a ZIP+4, which is normalized to its first 5 digits.
+/// Postal codes of locations outside the US are kept as the API returns them.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(transparent)]
+pub struct ZipCode(String);
+
+impl ZipCode {
+    /// Keep a non-US postal code without validating it.
+    pub(crate) fn from_postal_code(postal_code: &str) -> Self {
+        Self(postal_code.to_string())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for ZipCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for ZipCode {
+    type Err = ZipCodeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        let is_digits =
+            |part: &str, len: usize| part.len() == len && part.bytes().all(|b| b.is_ascii_digit());
+        let (zip, plus_four) = match trimmed.split_once('-') {
+            Some((zip, plus_four)) => (zip, Some(plus_four)),
+            None if is_digits(trimmed, 9) => (&trimmed[..5], Some(&trimmed[5..])),
+            None => (trimmed, None),
+        };
+        if is_digits(zip, 5) && plus_four.is_none_or(|plus_four| is_digits(plus_four, 4)) {
+            Ok(Self(zip.to_string()))
+        } else {
+            Err(ZipCodeError::Invalid(s.to_string()))
+        }
+    }
+}
+
+impl AsRef<str> for ZipCode {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Lets maps keyed by zip code be looked up with a `&str`.
+impl Borrow<str> for ZipCode {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq<str> for ZipCode {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for ZipCode {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_valid_zip() {
+        // Act
+        let zip_code: Result<ZipCode, _> = "12345".parse();
+
+        // Assert
+        assert_eq!(zip_code.unwrap(), "12345");
+    }
+
+    #[test]
+    fn parse_zip_plus_four_normalized() {
+        // Act
+        let dashed: Result<ZipCode, _> = "12345-6789".parse();
+        let undashed: Result<ZipCode, _> = "123456789".parse();
+
+        // Assert
+        assert_eq!(dashed.unwrap(), "12345");
+        assert_eq!(undashed.unwrap(), "12345");
+    }
+
+    #[test]
+    fn parse_garbage_rejected() {
+        for garbage in ["not a zip", "1234", "123456", "12345-67", "１２３４５", ""] {
+            assert!(
+                matches!(garbage.parse::<ZipCode>(), Err(ZipCodeError::Invalid(_))),
+                "{garbage:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn serde_transparent() {
+        // Act
+        let serialized = serde_json::to_string(&"12345".parse::<ZipCode>().unwrap()).unwrap();
+        let deserialized: ZipCode = serde_json::from_str("\"M5V 2T6\"").unwrap();
+
+        // Assert
+        assert_eq!(serialized, "\"12345\"");
+        assert_eq!(deserialized, "M5V 2T6");
+    }
+}
@@ -0,0 +1,111 @@
+//! Serves menus shaped like real menu service responses and checks what gets summarized from them.
+//! The fixtures carry every item type, sides, drinks, unavailable items and fields the API types ignore.
+
+use httpmock::prelude::*;
+use pepper_api::{
+    error::GetError,
+    menu::{Menu, Money, BOWL_PROTEINS},
+    ApiKey, LocationId,
+};
+
+const FAKE_API_KEY: &str = "fake-api-key";
+
+/// Serve `fixture` as the menu of `restaurant_id` and get its summary.
+async fn get_fixture_menu(restaurant_id: i32, fixture: &str) -> Result<Menu, GetError> {
+    let server = MockServer::start_async().await;
+    let body = std::fs::read_to_string(format!(
+        "{}/tests/fixtures/{fixture}",
+        env!("CARGO_MANIFEST_DIR")
+    ))
+    .unwrap();
+    let menu_mock = server
+        .mock_async(|when, then| {
+            when.method(GET).path(format!("/{restaurant_id}"));
+            then.status(200)
+                .header("Content-Type", "application/json")
+                .body(body);
+        })
+        .await;
+    let url = server.url("/$store");
+    let client = reqwest::Client::new();
+
+    let menu = Menu::get_custom(
+        &LocationId(restaurant_id),
+        &ApiKey::from_raw(FAKE_API_KEY),
+        &client,
+        Some(url.as_str()),
+    )
+    .await;
+    menu_mock.assert_async().await;
+    menu
+}
+
+fn normal_price(menu: &Menu, protein: &str) -> Option<Money> {
+    menu.bowl_price(protein).map(|price| price.normal_price)
+}
+
+#[tokio::test]
+async fn full_menu_prices_every_protein() {
+    // Act
+    let menu = get_fixture_menu(1001, "menu_full.json").await;
+
+    // Assert
+    assert!(menu.is_ok(), "Failed to get menu: {:?}", menu.unwrap_err());
+    let menu = menu.unwrap();
+    assert_eq!(menu.bowl_prices.len(), BOWL_PROTEINS.len());
+    assert_eq!(normal_price(&menu, "chicken"), Some(Money::from_cents(915)));
+    assert_eq!(normal_price(&menu, "steak"), Some(Money::from_cents(1085)));
+    assert_eq!(
+        normal_price(&menu, "chicken al pastor"),
+        Some(Money::from_cents(965))
+    );
+    assert_eq!(normal_price(&menu, "veggie"), Some(Money::from_cents(915)));
+    assert_eq!(menu.bowl_price("chicken").unwrap().calories, Some(180));
+}
+
+#[tokio::test]
+async fn full_menu_keeps_available_sides_only() {
+    // Act
+    let menu = get_fixture_menu(1001, "menu_full.json").await.unwrap();
+
+    // Assert
+    let sides: Vec<_> = menu.sides.keys().map(String::as_str).collect();
+    assert_eq!(
+        sides,
+        ["chips", "chips & guacamole", "chips & queso blanco"]
+    );
+    assert_eq!(
+        menu.sides["chips & guacamole"].normal_price,
+        Money::from_cents(495)
+    );
+}
+
+#[tokio::test]
+async fn missing_and_unavailable_proteins_are_left_out() {
+    // Act
+    let menu = get_fixture_menu(1002, "menu_missing_protein.json").await;
+
+    // Assert
+    assert!(menu.is_ok(), "Failed to get menu: {:?}", menu.unwrap_err());
+    let menu = menu.unwrap();
+    assert_eq!(menu.bowl_price("chicken al pastor"), None);
+    assert_eq!(menu.bowl_price("steak"), None);
+    assert_eq!(menu.bowl_prices.len(), BOWL_PROTEINS.len() - 2);
+    assert_eq!(
+        normal_price(&menu, "carnitas"),
+        Some(Money::from_cents(985))
+    );
+}
+
+#[tokio::test]
+async fn menu_without_bowls_is_an_error() {
+    // Act
+    let menu = get_fixture_menu(1003, "menu_no_bowls.json").await;
+
+    // Assert
+    let error = menu.unwrap_err();
+    assert!(
+        matches!(error, GetError::TranslateError(_)),
+        "Expected a translate error, got {error:?}"
+    );
+}